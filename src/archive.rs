@@ -0,0 +1,69 @@
+use crate::error;
+use crate::tasks;
+use crate::state;
+use crate::format;
+
+use std::fs;
+use std::path;
+
+/// Moves completed tasks (only those completed before the given date, if provided) into the
+/// archive folder of the vault, removing them from the index, dependency graph and the
+/// dependencies of other tasks. Returns the number of tasks archived.
+pub fn archive(before : Option<chrono::NaiveDate>, vault_folder : &path::Path, state : &mut state::State) -> Result<usize, error::Error> {
+    let archive_folder = vault_folder.join("archive");
+
+    let to_archive : Vec<_> = tasks::Task::load_all(vault_folder, true)?
+        .into_iter()
+        .filter(|t| {
+            match (t.data.completed, before) {
+                (Some(completed), Some(before)) => completed.date() < before,
+                (Some(_), None) => true,
+                (None, _) => false,
+            }
+        })
+        .collect();
+
+    if !to_archive.is_empty() && !archive_folder.exists() {
+        fs::create_dir(&archive_folder)?;
+    }
+
+    for task in &to_archive {
+        let id = task.data.id;
+        fs::rename(&task.path, archive_folder.join(format!("{}.toml", id)))?;
+
+        state.data.index.remove(task.data.name.clone(), id);
+        state.data.cache.remove(id);
+        // Archived tasks are complete, so they no longer need to be listed as dependencies.
+        if let (true, dependents) = state.data.deps.remove_node(id) {
+            for dependent in dependents {
+                let mut task = tasks::Task::load(dependent, vault_folder, false)?;
+                task.data.dependencies.remove(&id);
+                state.data.cache.insert(&task);
+                task.save()?;
+            }
+        }
+
+        println!("Archived task {} (ID: {})", format::task(&task.data.name), format::id(id));
+    }
+
+    Ok(to_archive.len())
+}
+
+/// Loads all archived tasks of a vault.
+pub fn load_all(vault_folder : &path::Path) -> Result<Vec<tasks::Task>, error::Error> {
+    let archive_folder = vault_folder.join("archive");
+
+    if !archive_folder.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut tasks = Vec::new();
+    for entry in fs::read_dir(archive_folder)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().map(|e| e == "toml").unwrap_or(false) {
+            tasks.push(tasks::Task::load_direct(path, true)?);
+        }
+    }
+
+    Ok(tasks)
+}
@@ -1,12 +1,15 @@
 use crate::tasks;
+use crate::config;
 use crate::tasks::Id;
 
+use std::cmp;
+use std::str;
 use std::path;
 
 impl Args {
-    pub fn accept_command() -> Command {
+    pub fn accept_command() -> Args {
         use clap::Parser;
-        Args::parse().command
+        Args::parse()
     }
 }
 
@@ -14,6 +17,36 @@ impl Args {
 pub struct Args {
     #[clap(subcommand)]
     pub command : Command,
+    /// Path to a configuration file to use instead of the default.
+    #[clap(long, global=true)]
+    pub config : Option<path::PathBuf>,
+    /// Format of the output, for scripting around commands which display tasks or stats (csv is
+    /// only supported by list and stats completed, with other commands giving human readable output).
+    #[clap(long, global=true, value_enum, default_value_t)]
+    pub format : OutputFormat,
+    /// Disable coloured output (also disabled by setting NO_COLOR).
+    #[clap(long, global=true)]
+    pub no_color : bool,
+    /// Run the command against this vault instead of the current one, without switching to it
+    /// (given before the command, as some commands have their own --vault flag).
+    #[clap(long="vault", value_name="VAULT")]
+    pub vault_override : Option<String>,
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+    Csv,
+}
+
+/// Formats which tasks can be imported from.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// An array of objects with a name, and optionally info, tags, priority, due and dependencies.
+    #[default]
+    Json,
 }
 
 #[derive(clap::Subcommand, Debug, PartialEq, Eq)]
@@ -23,37 +56,193 @@ pub enum Command {
     New {
         #[clap(short, long)]
         name : String,
+        /// Info for the task, or - to read it from standard input.
         #[clap(short, long)]
         info : Option<String>,
+        /// Write the info for the task in the info editor.
+        #[clap(long, conflicts_with="info")]
+        edit : bool,
         #[clap(short, long)]
         tag : Vec<String>,
         #[clap(short, long)]
         dependency : Vec<Id>,
+        /// Existing tasks (by ID or name) which should depend on the new task.
+        #[clap(short, long)]
+        blocks : Vec<String>,
+        /// Existing task (by ID or name) which the new task is a subtask of.
+        #[clap(long)]
+        parent : Option<String>,
         #[clap(short, long, value_enum)]
         priority : Option<tasks::Priority>,
-        /// Due date, [format: yyyy-mm-ddThh:mm:ss].
-        #[clap(long)]
+        /// Due date, [format: yyyy-mm-ddThh:mm:ss, yyyy-mm-dd, today, tomorrow, a weekday, or an offset such as +5d].
+        #[clap(long, value_parser=parse_datetime)]
         due : Option<chrono::NaiveDateTime>,
+        /// Date to start working on the task, in the same formats as the due date.
+        #[clap(long, value_parser=parse_datetime)]
+        scheduled : Option<chrono::NaiveDateTime>,
+        /// Estimated time to complete the task, [format: HH:MM].
+        #[clap(long)]
+        estimate : Option<tasks::Duration>,
+        /// Interval at which the task recurs, such as daily, weekly, 3d, 2w, 1mo or 1y.
+        #[clap(long)]
+        repeat : Option<tasks::Recurrence>,
+        /// Accept a due date more than 100 years away without asking for confirmation.
+        #[clap(short, long)]
+        yes : bool,
+    },
+    /// Create tasks in bulk from a file.
+    Import {
+        path : path::PathBuf,
+        /// Format of the file.
+        #[clap(long="input-format", value_enum, default_value_t)]
+        format : ImportFormat,
+    },
+    /// Move completed tasks out of the vault into its archive folder.
+    Archive {
+        /// Only archive tasks completed before this date, [format: yyyy-mm-dd].
+        #[clap(long, value_parser=parse_date)]
+        before : Option<chrono::NaiveDate>,
+    },
+    /// Move a task to another vault, where it is given a new ID.
+    Move {
+        id_or_name : String,
+        /// Name of the vault to move the task to.
+        vault : String,
     },
     /// Displays the specified task in detail.
     View {
         id_or_name : String,
+        /// Output the full task data as JSON, with the time tracked and dependency tree (same as --format json).
+        #[clap(long)]
+        json : bool,
+        /// Show the priority and due date of each task in the dependency tree.
+        #[clap(short, long)]
+        detailed : bool,
+    },
+    /// Read the info of a task in the pager.
+    Open {
+        id_or_name : String,
     },
-    /// Edit a task directly.
+    /// Edit a task directly, or change individual fields without opening the editor.
     Edit {
         id_or_name : String,
         /// Edit the info specifically in its own file.
-        #[clap(short, long)]
+        #[clap(short, long, conflicts_with_all=&["name", "priority", "due", "clear-due", "add-tag", "remove-tag"])]
         info : bool,
+        /// New name for the task.
+        #[clap(long)]
+        name : Option<String>,
+        #[clap(short, long, value_enum)]
+        priority : Option<tasks::Priority>,
+        /// New due date, in the same formats as for new tasks.
+        #[clap(long, value_parser=parse_datetime, conflicts_with="clear-due")]
+        due : Option<chrono::NaiveDateTime>,
+        /// Remove the due date of the task.
+        #[clap(long)]
+        clear_due : bool,
+        /// Tags to add to the task.
+        #[clap(long)]
+        add_tag : Vec<String>,
+        /// Tags to remove from the task.
+        #[clap(long)]
+        remove_tag : Vec<String>,
+        /// Accept a due date more than 100 years away without asking for confirmation.
+        #[clap(short, long)]
+        yes : bool,
+    },
+    /// Create an incomplete copy of a task, without its time entries.
+    Duplicate {
+        id_or_name : String,
+        /// Name of the copy, instead of the name of the original task.
+        #[clap(short, long)]
+        name : Option<String>,
+        /// Also copy all nested dependencies, linking the copies to each other.
+        #[clap(long)]
+        with_deps : bool,
+    },
+    /// Find tasks with identical names and merge them together.
+    Dedup {
+        /// Only treat tasks as duplicates if they also have identical tags.
+        #[clap(long)]
+        match_tags : bool,
+        /// List the duplicates without merging them.
+        #[clap(long)]
+        dry_run : bool,
     },
     /// Delete a task (move file to trash).
     Delete {
-        id_or_name : String,
+        #[clap(required=true)]
+        id_or_name : Vec<String>,
+        /// Delete without asking for confirmation.
+        #[clap(short, long)]
+        yes : bool,
+        /// Also delete all subtasks of the tasks.
+        #[clap(short, long)]
+        recursive : bool,
+        /// Move subtasks up to the parent of the deleted task, rather than deleting them.
+        #[clap(long, conflicts_with="recursive")]
+        reparent : bool,
+    },
+    /// Finds tasks with names (and optionally info) containing the query, ignoring case.
+    Search {
+        query : String,
+        /// Also search the info of each task.
+        #[clap(long)]
+        info : bool,
+        /// Treat the query as a regular expression.
+        #[clap(long)]
+        regex : bool,
+    },
+    /// Displays the most important task to work on next.
+    Next {
+        /// Also consider tasks with incomplete dependencies.
+        #[clap(long)]
+        include_blocked : bool,
+        /// Only consider tasks with at least one of these tags.
+        #[clap(short, long)]
+        tag : Vec<String>,
     },
     /// Mark a task as complete.
     Complete {
+        #[clap(required=true)]
+        id_or_name : Vec<String>,
+        /// Also complete all subtasks, and their subtasks in turn.
+        #[clap(long)]
+        cascade : bool,
+        /// Note on how the task was completed, appended to its info with a timestamp.
+        #[clap(long)]
+        note : Option<String>,
+        /// Complete the task even if it has incomplete dependencies.
+        #[clap(short, long)]
+        force : bool,
+    },
+    /// Add a dependency to a task.
+    Depend {
+        id_or_name : String,
+        /// ID of the task to depend on.
+        on : Id,
+    },
+    /// Remove a dependency from a task.
+    Undepend {
+        id_or_name : String,
+        /// ID of the task to no longer depend on.
+        from : Id,
+    },
+    /// Export the dependency graph in the Graphviz DOT format.
+    Graph {
+        /// File to write to, instead of printing.
+        #[clap(long)]
+        output : Option<path::PathBuf>,
+    },
+    /// Mark a complete task as incomplete.
+    Reopen {
         id_or_name : String,
     },
+    /// Rebuild the index, dependency graph and cached task metadata from the task files.
+    Reindex,
+    /// Revert the tasks changed by the most recent new, import, edit, complete, reopen, delete, track,
+    /// untrack, depend, undepend, checklist change or tag rename.
+    Undo,
     /// Run Git commands at the root of the vault.
     #[clap(trailing_var_arg=true)]
     Git {
@@ -74,24 +263,56 @@ pub enum Command {
         profile : Option<String>,
         #[clap(flatten)]
         options : ListOptions,
+        /// Save the list options used as the default for when none are provided.
+        #[clap(long)]
+        save_default : bool,
+        /// Clear the screen and list the tasks again every number of seconds, until interrupted.
+        #[clap(long, value_name="SECONDS", value_parser=clap::value_parser!(u64).range(1..), conflicts_with="save-default")]
+        watch : Option<u64>,
     },
     /// Adds the recommended svn:ignore property to the top level of the vault.
     #[clap(name="svn:ignore")]
     SvnIgnore,
     /// For tracking time against a task.
+    #[clap(subcommand_negates_reqs=true, args_conflicts_with_subcommands=true)]
     Track {
-        id_or_name : String,
-        duration : tasks::Duration,
+        #[clap(subcommand)]
+        timer : Option<TimerCommand>,
+        /// Tasks (by ID or name) to track time against, followed by the duration [format: HH:MM]
+        /// unless it is given with --duration. The same entry is added to each task.
+        #[clap(value_name="ID_OR_NAME|DURATION", required_unless_present="from-file")]
+        targets : Vec<String>,
+        /// Duration of the time entry, in place of giving it after the tasks [format: HH:MM].
+        #[clap(long)]
+        duration : Option<tasks::Duration>,
+        /// Import time entries from a CSV file, with rows of: id_or_name,date,duration,message
+        #[clap(long, conflicts_with_all=&["targets", "duration", "date", "message"])]
+        from_file : Option<path::PathBuf>,
         /// Date for the time entry [default: Today]
         #[clap(short, long)]
         date : Option<chrono::NaiveDate>,
         /// Message to identify the time entry.
         #[clap(short, long)]
         message : Option<String>,
+        /// Track time even if the task is already complete.
+        #[clap(short, long)]
+        force : bool,
+    },
+    /// Remove a time entry from a task.
+    Untrack {
+        id_or_name : String,
+        /// Number of the time entry, as shown when viewing the task.
+        index : usize,
     },
+    /// For adding and ticking off checklist items within a task.
+    #[clap(subcommand)]
+    Check(CheckCommand),
     /// For statistics about the state of your vault.
     #[clap(subcommand)]
     Stats(StatsCommand),
+    /// For managing tags across all tasks.
+    #[clap(subcommand)]
+    Tag(TagCommand),
     /// For making changes to global configuration.
     #[clap(subcommand)]
     Config(ConfigCommand),
@@ -104,17 +325,24 @@ pub enum Command {
     },
 }
 
-#[derive(clap::StructOpt, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde_with::serde_as]
+#[derive(clap::StructOpt, Default, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct ListOptions {
     /// Which columns to include.
     #[clap(short, value_enum)]
     pub column : Vec<Column>,
-    /// Field to order by [default: id].
+    /// Fields to order by, with later fields ordering tasks which are equal on earlier ones and any
+    /// remaining ties ordered by ID [default: id].
     #[clap(long, value_enum)]
-    pub order_by : Option<OrderBy>,
+    #[serde(default)]
+    #[serde_as(as = "serde_with::OneOrMany<_>")]
+    pub order_by : Vec<OrderBy>,
     /// Sort ascending on descending [default: asc].
     #[clap(long, value_enum)]
     pub order : Option<Order>,
+    /// Only include the first number of tasks, after sorting.
+    #[clap(short='n', long)]
+    pub limit : Option<usize>,
     /// Tags to include.
     #[clap(short, long)]
     pub tag : Vec<String>,
@@ -124,27 +352,167 @@ pub struct ListOptions {
     /// Priority levels to include.
     #[clap(short, long, value_enum)]
     pub priority : Vec<tasks::Priority>,
-    /// Only include tasks due before a certain date (inclusive).
-    #[clap(long)]
+    /// Only include tasks due before a certain date (inclusive), excluding tasks with no due date.
+    #[clap(long, value_parser=parse_date)]
     pub due_before : Option<chrono::NaiveDate>,
-    /// Only include tasks due after a certain date (inclusive).
-    #[clap(long)]
+    /// Only include tasks due after a certain date (inclusive), including tasks with no due date.
+    #[clap(long, value_parser=parse_date)]
     pub due_after : Option<chrono::NaiveDate>,
-    /// Only include tasks created before a certain date (inclusive).
+    /// Only include tasks which have a due date (pass =false to override a profile).
+    #[clap(long, conflicts_with="no-due", value_name="BOOL", min_values=0, require_equals=true, default_missing_value="true")]
+    pub has_due : Option<bool>,
+    /// Only include tasks which have no due date (pass =false to override a profile).
+    #[clap(long, value_name="BOOL", min_values=0, require_equals=true, default_missing_value="true")]
+    pub no_due : Option<bool>,
+    /// Only include incomplete tasks which are past their due date (pass =false to override a profile).
+    #[clap(long, conflicts_with="no-due", value_name="BOOL", min_values=0, require_equals=true, default_missing_value="true")]
+    pub overdue : Option<bool>,
+    /// Hide tasks which are scheduled to start in the future (pass =false to override a profile).
+    #[clap(long, value_name="BOOL", min_values=0, require_equals=true, default_missing_value="true")]
+    pub hide_unscheduled : Option<bool>,
+    /// Only include tasks created before a certain date or time (inclusive), [format: yyyy-mm-dd or yyyy-mm-ddThh:mm:ss].
     #[clap(long)]
-    pub created_before : Option<chrono::NaiveDate>,
-    /// Only include tasks created after a certain date (inclusive).
+    pub created_before : Option<DateOrDateTime>,
+    /// Only include tasks created after a certain date or time (inclusive), [format: yyyy-mm-dd or yyyy-mm-ddThh:mm:ss].
     #[clap(long)]
-    pub created_after : Option<chrono::NaiveDate>,
-    /// Include completed tasks in the list.
+    pub created_after : Option<DateOrDateTime>,
+    /// Only include tasks completed within the given duration of now, HH:MM (implies including completed tasks).
     #[clap(long)]
-    pub include_completed : bool,
-    /// Only include tasks with no (incomplete) dependencies [alias: bottom-level].
-    #[clap(long, alias="bottom-level")]
-    pub no_dependencies : bool,
-    /// Only include tasks with no dependents [alias: top-level].
-    #[clap(long, alias="top-level")]
-    pub no_dependents : bool,
+    pub completed_within : Option<tasks::Duration>,
+    /// Only include tasks completed before a certain date (inclusive, implies including completed tasks).
+    #[clap(long, value_parser=parse_date)]
+    pub completed_before : Option<chrono::NaiveDate>,
+    /// Only include tasks completed after a certain date (inclusive, implies including completed tasks).
+    #[clap(long, value_parser=parse_date)]
+    pub completed_after : Option<chrono::NaiveDate>,
+    /// Include completed tasks in the list (pass =false to override a profile).
+    #[clap(long, value_name="BOOL", min_values=0, require_equals=true, default_missing_value="true")]
+    pub include_completed : Option<bool>,
+    /// List archived tasks instead of those in the vault (pass =false to override a profile).
+    #[clap(long, value_name="BOOL", min_values=0, require_equals=true, default_missing_value="true")]
+    pub archived : Option<bool>,
+    /// Only include tasks with no (incomplete) dependencies (pass =false to override a profile) [alias: bottom-level].
+    #[clap(long, alias="bottom-level", value_name="BOOL", min_values=0, require_equals=true, default_missing_value="true")]
+    pub no_dependencies : Option<bool>,
+    /// Only include tasks with no dependents (pass =false to override a profile) [alias: top-level].
+    #[clap(long, alias="top-level", value_name="BOOL", min_values=0, require_equals=true, default_missing_value="true")]
+    pub no_dependents : Option<bool>,
+    /// Only include direct subtasks of this task.
+    #[clap(long, value_name="ID")]
+    pub children_of : Option<Id>,
+    /// Display a separate table for each group of tasks.
+    #[clap(long, value_enum)]
+    pub group_by : Option<GroupBy>,
+    /// Only print the number of tasks which would be listed (pass =false to override a profile).
+    #[clap(long, value_name="BOOL", min_values=0, require_equals=true, default_missing_value="true")]
+    pub count : Option<bool>,
+}
+
+/// Parses a date and time, which may be absolute or relative to now. Dates without a time, today,
+/// tomorrow and weekdays (the next occurrence, optionally preceded by "next") are taken as the end
+/// of that day, and offsets such as +2h, +5d and +1w are added to the current time.
+pub fn parse_datetime(s : &str) -> Result<chrono::NaiveDateTime, String> {
+    use chrono::Datelike;
+
+    let invalid = || format!("invalid date \"{}\", expected yyyy-mm-ddThh:mm:ss, yyyy-mm-dd, today, tomorrow, a weekday (such as next monday), or an offset such as +2h, +5d or +1w", s);
+
+    let now = chrono::Local::now().naive_local();
+    let end_of_day = |date : chrono::NaiveDate| date.and_hms_opt(23, 59, 59).unwrap();
+
+    if let Ok(datetime) = s.parse::<chrono::NaiveDateTime>() {
+        return Ok(datetime);
+    }
+    if let Ok(date) = s.parse::<chrono::NaiveDate>() {
+        return Ok(end_of_day(date));
+    }
+
+    let keyword = s.trim().to_lowercase();
+
+    if let Some(offset) = keyword.strip_prefix('+') {
+        let split = offset.find(|c : char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let (count, unit) = offset.split_at(split);
+        let count = count.parse::<i64>().map_err(|_| invalid())?;
+
+        let offset = match unit {
+            "h" => chrono::Duration::hours(count),
+            "d" => chrono::Duration::days(count),
+            "w" => chrono::Duration::weeks(count),
+            _ => return Err(invalid()),
+        };
+
+        return now.checked_add_signed(offset).ok_or_else(invalid);
+    }
+
+    match keyword.as_str() {
+        "today" => Ok(end_of_day(now.date())),
+        "tomorrow" => Ok(end_of_day(now.date() + chrono::Duration::days(1))),
+        weekday => {
+            let weekday = weekday.strip_prefix("next ").unwrap_or(weekday).parse::<chrono::Weekday>().map_err(|_| invalid())?;
+
+            // The next occurrence of the weekday, never today.
+            let days_ahead = (7 + weekday.num_days_from_monday() - now.weekday().num_days_from_monday() - 1) % 7 + 1;
+            Ok(end_of_day(now.date() + chrono::Duration::days(i64::from(days_ahead))))
+        }
+    }
+}
+
+/// Parses a date in any of the formats accepted by `parse_datetime`.
+pub fn parse_date(s : &str) -> Result<chrono::NaiveDate, String> {
+    parse_datetime(s).map(|datetime| datetime.date())
+}
+
+/// Parses a colour given as hex (#rrggbb, with the # optional) or as r,g,b.
+pub fn parse_colour(s : &str) -> Result<(u8, u8, u8), String> {
+    let invalid = || format!("invalid colour \"{}\", expected #rrggbb or r,g,b", s);
+
+    let components : Vec<u8> = if s.contains(',') {
+        s.split(',')
+            .map(|c| c.trim().parse::<u8>().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?
+    }
+    else {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        (0..6).step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?
+    };
+
+    match components[..] {
+        [r, g, b] => Ok((r, g, b)),
+        _ => Err(invalid()),
+    }
+}
+
+/// A date, or a date with a time of day, for filters which may be day or time precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum DateOrDateTime {
+    DateTime(chrono::NaiveDateTime),
+    Date(chrono::NaiveDate),
+}
+
+impl str::FromStr for DateOrDateTime {
+    type Err = chrono::ParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        match s.parse::<chrono::NaiveDateTime>() {
+            Ok(datetime) => Ok(DateOrDateTime::DateTime(datetime)),
+            Err(_) => s.parse::<chrono::NaiveDate>().map(DateOrDateTime::Date),
+        }
+    }
+}
+
+impl DateOrDateTime {
+    /// Compares a datetime to this, only comparing the date if no time was provided.
+    pub fn compare(&self, datetime : &chrono::NaiveDateTime) -> cmp::Ordering {
+        match self {
+            DateOrDateTime::DateTime(bound) => datetime.cmp(bound),
+            DateOrDateTime::Date(bound) => datetime.date().cmp(bound),
+        }
+    }
 }
 
 #[derive(Default, Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
@@ -159,9 +527,21 @@ pub enum Column {
     Due,
     Priority,
     Created,
+    Modified,
     Tracked,
     Tags,
     Status,
+    CompletedAge,
+    Scheduled,
+    Estimate,
+    Checklist,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
+pub enum GroupBy {
+    Tag,
+    Priority,
+    Status,
 }
 
 #[derive(Default, Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
@@ -172,7 +552,52 @@ pub enum OrderBy {
     Due,
     Priority,
     Created,
+    Modified,
     Tracked,
+    /// Most urgent first, by a score combining priority, how soon the task is due and its age.
+    Score,
+}
+
+#[derive(clap::Subcommand, Debug, PartialEq, Eq)]
+pub enum CheckCommand {
+    /// Adds an item to the end of a task's checklist.
+    Add {
+        id_or_name : String,
+        text : String,
+    },
+    /// Ticks off a checklist item, or unticks it if it is already done.
+    Toggle {
+        id_or_name : String,
+        /// Number of the checklist item, as shown when viewing the task.
+        index : usize,
+    },
+}
+
+#[derive(clap::Subcommand, Debug, PartialEq, Eq)]
+pub enum TagCommand {
+    /// Renames a tag on all tasks which have it.
+    Rename {
+        old : String,
+        new : String,
+    },
+    /// Lists all tags along with how many tasks have each of them.
+    List,
+}
+
+#[derive(clap::Subcommand, Debug, PartialEq, Eq)]
+pub enum TimerCommand {
+    /// Starts a timer for a task, only one of which can run at a time.
+    Start {
+        id_or_name : String,
+    },
+    /// Stops the running timer, adding the elapsed time as an entry against its task.
+    Stop {
+        /// Message to identify the time entry.
+        #[clap(short, long)]
+        message : Option<String>,
+    },
+    /// Displays the running timer.
+    Status,
 }
 
 #[derive(clap::Subcommand, Debug, PartialEq, Eq)]
@@ -181,11 +606,79 @@ pub enum StatsCommand {
     Tracked {
         #[clap(short, long, default_value_t=7)]
         days : u16,
+        /// Only include activity since stats were last viewed (falls back to --days on first use).
+        #[clap(long)]
+        since_last : bool,
+        /// Only include activity from this many calendar weeks, starting from the configured week start (including the current week).
+        #[clap(long, value_parser=clap::value_parser!(u16).range(1..), conflicts_with_all=&["days", "since-last", "months"])]
+        weeks : Option<u16>,
+        /// Only include activity from this many calendar months, starting from the first of the month (including the current month).
+        #[clap(long, value_parser=clap::value_parser!(u16).range(1..), conflicts_with_all=&["days", "since-last"])]
+        months : Option<u16>,
+        /// Only print the total time tracked.
+        #[clap(long)]
+        count_only : bool,
+        /// Only include time tracked on tasks with this tag.
+        #[clap(short, long)]
+        tag : Option<String>,
+        /// Show the time tracked on each day instead of on each tag.
+        #[clap(long, conflicts_with="count-only")]
+        by_day : bool,
+    },
+    /// View recent time entries along with their messages.
+    Entries {
+        #[clap(short, long, default_value_t=7)]
+        days : u16,
+        /// Only include activity since stats were last viewed (falls back to --days on first use).
+        #[clap(long)]
+        since_last : bool,
+        /// Only include entries from tasks with these tags.
+        #[clap(short, long)]
+        tag : Vec<String>,
+    },
+    /// View estimated against tracked time for incomplete tasks with estimates.
+    Estimates,
+    /// View incomplete tasks which are overdue, due today or due soon.
+    Due {
+        /// Number of days ahead to include upcoming tasks from.
+        #[clap(short, long, default_value_t=7)]
+        days : u16,
+    },
+    /// View remaining estimated work per day, for tasks with estimates.
+    Burndown {
+        #[clap(short, long, default_value_t=7)]
+        days : u16,
+    },
+    /// View the number of tasks completed in each ISO week (starting on Monday), including the current week.
+    Velocity {
+        #[clap(short, long, default_value_t=8, value_parser=clap::value_parser!(u16).range(1..))]
+        weeks : u16,
     },
     /// View recently completed tasks.
     Completed {
         #[clap(short, long, default_value_t=7)]
         days : u16,
+        /// Only include activity since stats were last viewed (falls back to --days on first use).
+        #[clap(long)]
+        since_last : bool,
+        /// Only include activity from this many calendar weeks, starting from the configured week start (including the current week).
+        #[clap(long, value_parser=clap::value_parser!(u16).range(1..), conflicts_with_all=&["days", "since-last", "months"])]
+        weeks : Option<u16>,
+        /// Only include activity from this many calendar months, starting from the first of the month (including the current month).
+        #[clap(long, value_parser=clap::value_parser!(u16).range(1..), conflicts_with_all=&["days", "since-last"])]
+        months : Option<u16>,
+        /// Only include tasks completed on or after this date.
+        #[clap(long, value_parser=parse_date, conflicts_with_all=&["days", "since-last", "weeks", "months"])]
+        from : Option<chrono::NaiveDate>,
+        /// Only include tasks completed on or before this date.
+        #[clap(long, value_parser=parse_date, conflicts_with_all=&["days", "since-last", "weeks", "months"])]
+        to : Option<chrono::NaiveDate>,
+        /// Output the name, completion date, tags and tracked time of each task as CSV (same as --format csv).
+        #[clap(long, conflicts_with="count-only")]
+        csv : bool,
+        /// Only print the number of tasks completed.
+        #[clap(long)]
+        count_only : bool,
     },
 }
 
@@ -196,9 +689,100 @@ pub enum ConfigCommand {
         /// Command to launch editor. Omit to view current editor.
         editor : Option<String>,
     },
+    /// For checking or changing the pager command used to read task info.
+    Pager {
+        /// Command to launch pager. Omit to view current pager.
+        pager : Option<String>,
+    },
+    /// For checking or changing the colour a tag is displayed in.
+    TagColour {
+        tag : String,
+        /// Colour as hex (#rrggbb) or r,g,b. Omit to view the current colour.
+        #[clap(value_parser=parse_colour)]
+        colour : Option<(u8, u8, u8)>,
+        /// Display the tag without a colour again.
+        #[clap(long, conflicts_with="colour")]
+        unset : bool,
+    },
+    /// For checking or changing the text editor command used for task info.
+    InfoEditor {
+        /// Command to launch editor for task info. Omit to view current info editor.
+        editor : Option<String>,
+        /// Use the default text editor for task info again.
+        #[clap(long, conflicts_with="editor")]
+        unset : bool,
+    },
+    /// For checking or changing how task files are removed on delete.
+    DeleteMode {
+        /// Method used to remove task files. Omit to view current delete mode.
+        #[clap(value_enum)]
+        mode : Option<config::DeleteMode>,
+    },
+    /// For checking or changing how tags are ordered in the list command.
+    TagOrder {
+        /// Ordering of tags. Omit to view current tag order.
+        #[clap(value_enum)]
+        order : Option<config::TagOrder>,
+    },
+    /// For checking or changing the first day of the week.
+    WeekStart {
+        /// Day the week starts on. Omit to view current week start.
+        #[clap(value_enum)]
+        day : Option<config::Weekday>,
+    },
+    /// For checking or changing how close to a due date it is shown as very close or close. Omit
+    /// both to view the current thresholds.
+    DueThresholds {
+        /// Hours before a due date at which it is shown as very close.
+        #[clap(long)]
+        very_close_hours : Option<u32>,
+        /// Days before a due date at which it is shown as close.
+        #[clap(long)]
+        close_days : Option<u32>,
+    },
+    /// For checking or changing how much priority, due date and age count towards the score
+    /// tasks can be ordered by. Omit all to view the current weights.
+    ScoreWeights {
+        /// Score for each priority level above backlog.
+        #[clap(long)]
+        priority : Option<u32>,
+        /// Score for a task which is due now or overdue, falling to half for a task due in a week.
+        #[clap(long)]
+        due : Option<u32>,
+        /// Score for each 30 days since the task was created.
+        #[clap(long)]
+        age : Option<u32>,
+    },
     /// For working with profiles for the list command.
     #[clap(subcommand)]
     Profile(ProfileCommand),
+    /// Shows the effective configuration, including vault specific profiles.
+    #[clap(alias="list")]
+    Show,
+    /// Writes settings and profiles to a file, for setting up toru on another machine.
+    Export {
+        /// File to write to. Omit to print the settings.
+        path : Option<path::PathBuf>,
+        /// Include the names and paths of vaults.
+        #[clap(long)]
+        include_vaults : bool,
+    },
+    /// Merges in settings and profiles from a file created by export.
+    Import {
+        path : path::PathBuf,
+        /// Replace profiles which already exist, rather than skipping them.
+        #[clap(long)]
+        overwrite : bool,
+    },
+    /// For checking or changing the list options used when no profile is given, which any
+    /// arguments to list are combined with. Omit options to view the current default.
+    DefaultList {
+        #[clap(flatten)]
+        options : ListOptions,
+        /// Remove the default list options.
+        #[clap(long)]
+        clear : bool,
+    },
 }
 
 #[derive(clap::Subcommand, Debug, PartialEq, Eq)]
@@ -206,15 +790,26 @@ pub enum ProfileCommand {
     New {
         /// Name of the new profile.
         name : String,
+        /// Only make the profile available in the current vault.
+        #[clap(long)]
+        vault : bool,
         #[clap(flatten)]
-        options : ListOptions,
+        options : Box<ListOptions>,
     },
     Delete {
         /// Name of the profile to delete.
         name : String,
+        /// Delete the profile scoped to the current vault, rather than the global one.
+        #[clap(long)]
+        vault : bool,
     },
     /// List names of currently set up profiles.
     List,
+    /// Show the options stored in a profile.
+    Show {
+        /// Name of the profile to show.
+        name : String,
+    },
 }
 
 #[derive(clap::Subcommand, Debug, PartialEq, Eq)]
@@ -239,10 +834,42 @@ pub enum VaultCommand {
     },
     /// Lists all configured vaults.
     List,
+    /// Displays the current vault.
+    Current {
+        /// Only print the path of the vault.
+        #[clap(long, conflicts_with="name-only")]
+        path_only : bool,
+        /// Only print the name of the vault.
+        #[clap(long)]
+        name_only : bool,
+    },
+    /// Rewrites all task files in the current vault in a consistent format.
+    Tidy,
+    /// Displays a summary of the tasks in the current vault.
+    Info,
+    /// Checks that the index and dependency graph agree with the task files.
+    Check {
+        /// Rebuild the index and dependency graph, removing dependencies on missing tasks.
+        #[clap(long)]
+        fix : bool,
+    },
     /// For renaming an already set up vault.
     Rename {
         old_name : String,
         new_name : String,
-    }
+    },
+    /// Saves the tasks, state and configuration of the current vault to a zip archive.
+    Backup {
+        /// Path of the archive (defaults to a timestamped file alongside the vault).
+        output : Option<path::PathBuf>,
+    },
+    /// Recreates a vault from an archive created by backup, and connects it under the given name.
+    Restore {
+        archive : path::PathBuf,
+        name : String,
+        /// Folder to restore the vault into (defaults to a folder of the vault's name alongside the archive).
+        #[clap(long)]
+        path : Option<path::PathBuf>,
+    },
 }
 
@@ -0,0 +1,163 @@
+use crate::error;
+use crate::config;
+use crate::format;
+
+use std::io;
+use std::fs;
+use std::path;
+use std::io::Write;
+use chrono::{Datelike, Timelike};
+
+/// Files at the top level of a vault which are included in backups.
+const FILES : [&str; 3] = ["state.toml", "config.toml", "journal.jsonl"];
+
+/// Folders of task files which are included in backups.
+const FOLDERS : [&str; 2] = ["tasks", "archive"];
+
+/// Bundles the task files, state and configuration of a vault into a zip archive, by default
+/// named with the current time and placed alongside the vault. Returns the path of the archive.
+pub fn backup(name : &str, output : Option<path::PathBuf>, vault_folder : &path::Path) -> Result<path::PathBuf, error::Error> {
+    let output = output.unwrap_or_else(|| {
+        let timestamp = chrono::Local::now().naive_local().format("%Y-%m-%d-%H%M%S");
+        let file_name = format!("{}-backup-{}.zip", name, timestamp);
+        match vault_folder.parent() {
+            Some(parent) => parent.join(file_name),
+            None => path::PathBuf::from(file_name),
+        }
+    });
+
+    if output.exists() {
+        return Err(error::Error::Generic(format!("The file {} already exists", format::file(&output.display().to_string()))));
+    }
+
+    let mut entries = Vec::new();
+
+    for file in FILES {
+        let path = vault_folder.join(file);
+        if path.is_file() {
+            entries.push((String::from(file), path));
+        }
+    }
+
+    for folder in FOLDERS {
+        let folder_path = vault_folder.join(folder);
+        if !folder_path.is_dir() {
+            continue;
+        }
+
+        for entry in fs::read_dir(folder_path)? {
+            let path = entry?.path();
+            let file_name = path.file_name().and_then(|f| f.to_str()).unwrap_or_default();
+            // Leaves out temporary files and anything else which isn't a task.
+            if path.is_file() && is_task_file(file_name) {
+                entries.push((format!("{}/{}", folder, file_name), path));
+            }
+        }
+    }
+
+    entries.sort();
+
+    let mut writer = zip::ZipWriter::new(fs::File::create(&output)?);
+    // Zip timestamps have no time zone, so local time is used.
+    let now = chrono::Local::now().naive_local();
+    let modified = zip::DateTime::from_date_and_time(now.year() as u16, now.month() as u8, now.day() as u8, now.hour() as u8, now.minute() as u8, now.second() as u8)
+        .unwrap_or_default();
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(modified);
+
+    for (name, path) in entries {
+        writer.start_file(name, options)?;
+        writer.write_all(&fs::read(path)?)?;
+    }
+
+    writer.finish()?;
+
+    Ok(output)
+}
+
+/// Recreates a vault from a backup archive and adds it to the configuration under the given name,
+/// by default in a folder of that name alongside the archive. The archive is checked before
+/// anything is written. Returns the path of the restored vault.
+pub fn restore(archive_path : &path::Path, name : String, path : Option<path::PathBuf>, config : &mut config::Config) -> Result<path::PathBuf, error::Error> {
+    if config.contains_name(&name) {
+        return Err(error::Error::Generic(format!("A vault named \"{}\" already exists", name)));
+    }
+
+    let path = path.unwrap_or_else(|| {
+        archive_path.parent().unwrap_or_else(|| path::Path::new(".")).join(&name)
+    });
+
+    if config.contains_path(&path) {
+        return Err(error::Error::Generic(format!("A vault at the path {:?} already exists", path)));
+    }
+
+    if path.exists() && (!path.is_dir() || path.read_dir()?.next().is_some()) {
+        return Err(error::Error::Generic(String::from("The specified folder already exists and contains other data, please provide a path to a new or empty folder")));
+    }
+
+    let invalid = |reason : String| {
+        error::Error::Generic(format!("{} is not a vault backup, as {}", format::file(&archive_path.display().to_string()), reason))
+    };
+
+    let mut archive = zip::ZipArchive::new(fs::File::open(archive_path)?)
+        .map_err(|err| invalid(format!("it could not be read as a zip archive - {}", err)))?;
+
+    let mut has_state = false;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let entry_name = String::from(entry.name());
+
+        if !is_backup_entry(&entry_name) {
+            return Err(invalid(format!("it contains the unexpected file {}", format::file(&entry_name))));
+        }
+
+        if entry_name == "state.toml" {
+            let mut contents = String::new();
+            io::Read::read_to_string(&mut entry, &mut contents)?;
+            toml::from_str::<toml::Value>(&contents)
+                .map_err(|err| invalid(format!("its {} could not be read - {}", format::file("state.toml"), err)))?;
+
+            has_state = true;
+        }
+    }
+
+    if !has_state {
+        return Err(invalid(format!("it is missing the {} file", format::file("state.toml"))));
+    }
+
+    fs::create_dir_all(path.join("tasks"))?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        // Entries were checked above, so only contain known files and folders.
+        let target = path.join(entry.name());
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        io::copy(&mut entry, &mut fs::File::create(target)?)?;
+    }
+
+    config.add(name, path.clone());
+
+    Ok(path)
+}
+
+/// Whether a file name is that of a task, being its ID with a `.toml` extension.
+fn is_task_file(file_name : &str) -> bool {
+    file_name
+        .strip_suffix(".toml")
+        .map(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or(false)
+}
+
+/// Whether a path within an archive is one which a backup would contain.
+fn is_backup_entry(name : &str) -> bool {
+    FILES.contains(&name) || match name.split_once('/') {
+        Some((folder, file_name)) => FOLDERS.contains(&folder) && is_task_file(file_name),
+        None => false,
+    }
+}
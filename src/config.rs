@@ -2,7 +2,11 @@ use crate::args;
 use crate::error;
 use crate::format;
 
+use std::fs;
+use std::env;
+use std::fmt;
 use std::path;
+use std::collections::BTreeMap;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
@@ -10,9 +14,143 @@ pub struct Config {
     /// Paths for all vaults, ordered according to recent usage, with current at the front.
     pub vaults : Vec<(String, path::PathBuf)>,
     pub editor : String,
+    /// Editor used for task info, falling back to the editor when not set.
+    pub info_editor : Option<String>,
+    /// Pager used to read task info.
+    pub pager : String,
     pub profiles : Vec<Profile>,
+    /// How task files are removed when a task is deleted.
+    pub delete_mode : DeleteMode,
+    /// How tags are ordered in the tags column of the list command.
+    pub tag_order : TagOrder,
+    /// First day of the week, for views which group by week.
+    pub week_start : Weekday,
+    /// Hours before a due date at which it is shown as very close.
+    pub due_very_close_hours : u32,
+    /// Days before a due date at which it is shown as close.
+    pub due_close_days : u32,
+    /// Weights used to order tasks by score.
+    pub score_weights : ScoreWeights,
+    /// Colours which tags are displayed in, with other tags left uncoloured.
+    pub tag_colours : BTreeMap<String, (u8, u8, u8)>,
+    /// Vault used in place of the current vault for this invocation only, which is not saved.
+    #[serde(skip)]
+    vault_override : Option<String>,
 }
 
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Weekday {
+    #[default]
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl From<Weekday> for chrono::Weekday {
+    fn from(weekday : Weekday) -> Self {
+        match weekday {
+            Weekday::Monday => chrono::Weekday::Mon,
+            Weekday::Tuesday => chrono::Weekday::Tue,
+            Weekday::Wednesday => chrono::Weekday::Wed,
+            Weekday::Thursday => chrono::Weekday::Thu,
+            Weekday::Friday => chrono::Weekday::Fri,
+            Weekday::Saturday => chrono::Weekday::Sat,
+            Weekday::Sunday => chrono::Weekday::Sun,
+        }
+    }
+}
+
+impl fmt::Display for Weekday {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Weekday::Monday => write!(f, "monday"),
+            Weekday::Tuesday => write!(f, "tuesday"),
+            Weekday::Wednesday => write!(f, "wednesday"),
+            Weekday::Thursday => write!(f, "thursday"),
+            Weekday::Friday => write!(f, "friday"),
+            Weekday::Saturday => write!(f, "saturday"),
+            Weekday::Sunday => write!(f, "sunday"),
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TagOrder {
+    /// Sort tags alphabetically.
+    #[default]
+    Alphabetical,
+    /// Sort tags by how many tasks in the vault use them, most used first.
+    Frequency,
+}
+
+impl fmt::Display for TagOrder {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagOrder::Alphabetical => write!(f, "alphabetical"),
+            TagOrder::Frequency => write!(f, "frequency"),
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeleteMode {
+    /// Move the file to the system trash.
+    #[default]
+    Trash,
+    /// Permanently remove the file.
+    Remove,
+    /// Remove the file with `git rm` so the deletion is staged.
+    GitRm,
+}
+
+impl fmt::Display for DeleteMode {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeleteMode::Trash => write!(f, "trash"),
+            DeleteMode::Remove => write!(f, "remove"),
+            DeleteMode::GitRm => write!(f, "git-rm"),
+        }
+    }
+}
+
+/// How much each part of a task contributes to its score, when ordering tasks by score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ScoreWeights {
+    /// Score for each priority level above backlog.
+    pub priority : u32,
+    /// Score for a task which is due now or overdue, falling to half for a task due in a week.
+    pub due : u32,
+    /// Score for each 30 days since the task was created.
+    pub age : u32,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            priority : 10,
+            due : 20,
+            age : 1,
+        }
+    }
+}
+
+impl fmt::Display for ScoreWeights {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "priority {}, due {}, age {}", self.priority, self.due, self.age)
+    }
+}
+
+/// Reserved profile name for the options used by the list command when none are provided.
+pub static DEFAULT_PROFILE : &str = "default";
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Profile {
     name : String,
@@ -24,22 +162,65 @@ impl Default for Config {
         Self {
             vaults : Vec::default(),
             editor : String::from("vim"),
+            info_editor : None,
+            pager : String::from("less"),
             profiles : Vec::default(),
+            delete_mode : DeleteMode::default(),
+            tag_order : TagOrder::default(),
+            week_start : Weekday::default(),
+            due_very_close_hours : 24,
+            due_close_days : 5,
+            score_weights : ScoreWeights::default(),
+            tag_colours : BTreeMap::new(),
+            vault_override : None,
         }
     }
 }
 
 impl Config {
     pub fn current_vault(&self) -> Result<&(String, path::PathBuf), error::Error> {
-        self.vaults.get(0).ok_or_else(|| error::Error::Generic(String::from("The attempted operation requires a vault, none of which have been set up")))
+        match &self.vault_override {
+            Some(name) => self.get_vault(name),
+            None => self.vaults.get(0).ok_or_else(|| error::Error::Generic(String::from("The attempted operation requires a vault, none of which have been set up"))),
+        }
+    }
+
+    /// Uses the given vault in place of the current vault, without changing which vault is
+    /// current.
+    pub fn override_vault(&mut self, name : &str) -> Result<(), error::Error> {
+        let name = self.get_vault(name)?.0.clone();
+        self.vault_override = Some(name);
+        Ok(())
     }
 
-    pub fn save(self) -> Result<(), error::Error> {
-        Ok(confy::store::<Config>("toru", self)?)
+    /// Gets the path to the configuration file if it has been overridden, either by the provided
+    /// path or by the `TORU_CONFIG_DIR` environment variable (in that order of precedence).
+    fn override_path(path : Option<&path::Path>) -> Option<path::PathBuf> {
+        match path {
+            Some(path) => Some(path.to_path_buf()),
+            None => {
+                env::var_os("TORU_CONFIG_DIR")
+                    .filter(|dir| !dir.is_empty())
+                    .map(|dir| path::PathBuf::from(dir).join("config.toml"))
+            }
+        }
+    }
+
+    /// Saves the configuration, to the provided path if given and otherwise to the default location.
+    pub fn save(self, path : Option<&path::Path>) -> Result<(), error::Error> {
+        match Config::override_path(path) {
+            Some(path) => Ok(confy::store_path(path, self)?),
+            None => Ok(confy::store::<Config>("toru", self)?),
+        }
     }
 
-    pub fn load() -> Result<Config, error::Error> {
-        Ok(confy::load::<Config>("toru")?)
+    /// Loads the configuration, from the provided path if given and otherwise from the default
+    /// location.
+    pub fn load(path : Option<&path::Path>) -> Result<Config, error::Error> {
+        match Config::override_path(path) {
+            Some(path) => Ok(confy::load_path::<Config>(path)?),
+            None => Ok(confy::load::<Config>("toru")?),
+        }
     }
 
     pub fn contains_name(&self, name : &String) -> bool {
@@ -50,28 +231,43 @@ impl Config {
         self.vaults.iter().any(|(_, p)| p == path)
     }
 
-    pub fn rename_vault(&mut self, old_name : &String, new_name : String) -> Result<(), error::Error> {
-        let mut to_change = None;
-
-        for (name, _) in &mut self.vaults {
-            if *name == new_name {
-                return Err(error::Error::Generic(format!("A vault named {} already exists", format::vault(&new_name))));
-            }
+    /// Finds the index of the vault with the given name, falling back to a vault which the name is a
+    /// unique prefix of.
+    fn resolve_vault(&self, name : &str) -> Result<usize, error::Error> {
+        if let Some(index) = self.vaults.iter().position(|(n, _)| n == name) {
+            return Ok(index);
+        }
 
-            if name == old_name {
-                to_change = Some(name);
+        let candidates : Vec<_> = self.vaults
+            .iter()
+            .enumerate()
+            .filter(|(_, (n, _))| n.starts_with(name))
+            .collect();
+
+        match candidates[..] {
+            [(index, _)] => Ok(index),
+            [] => Err(error::Error::Generic(format!("No vault by the name {} exists", format::vault(name)))),
+            _ => {
+                let names : Vec<_> = candidates.iter().map(|(_, (n, _))| format::vault(n).to_string()).collect();
+                Err(error::Error::Generic(format!("Multiple vaults ({}) start with {}", names.join(", "), format::vault(name))))
             }
         }
+    }
 
-        match to_change {
-            Some(name) => {
-                *name = new_name;
-                Ok(())
-            },
-            None => {
-                Err(error::Error::Generic(format!("No vault named {} exists", format::vault(old_name))))
-            }
+    /// Gets the name and path of a vault, by name or unique prefix.
+    pub fn get_vault(&self, name : &str) -> Result<&(String, path::PathBuf), error::Error> {
+        Ok(&self.vaults[self.resolve_vault(name)?])
+    }
+
+    /// Renames a vault, returning the resolved old name.
+    pub fn rename_vault(&mut self, old_name : &str, new_name : String) -> Result<String, error::Error> {
+        if self.contains_name(&new_name) {
+            return Err(error::Error::Generic(format!("A vault named {} already exists", format::vault(&new_name))));
         }
+
+        let index = self.resolve_vault(old_name)?;
+        let (name, _) = &mut self.vaults[index];
+        Ok(std::mem::replace(name, new_name))
     }
 
     /// Adds the vault to the configuration.
@@ -82,28 +278,17 @@ impl Config {
         self.vaults.push((name, path));
     }
 
-    pub fn remove(&mut self, name : &String) -> Result<path::PathBuf, error::Error> {
-        match self.vaults.iter().position(|(n, _)| n == name) {
-            Some(index) => {
-                let (_, path) = self.vaults.swap_remove(index);
-                Ok(path)
-            },
-            None => {
-                Err(error::Error::Generic(format!("No vault by the name {} exists", format::vault(name))))
-            }
-        }
+    /// Removes a vault from the configuration, returning its resolved name and path.
+    pub fn remove(&mut self, name : &str) -> Result<(String, path::PathBuf), error::Error> {
+        let index = self.resolve_vault(name)?;
+        Ok(self.vaults.swap_remove(index))
     }
 
-    pub fn switch(&mut self, name : &String) -> Result<(), error::Error> {
-        match self.vaults.iter().position(|(n, _)| n == name) {
-            Some(index) => {
-                self.vaults.swap(index, 0);
-                Ok(())
-            },
-            None => {
-                Err(error::Error::Generic(format!("No vault by the name {} exists", format::vault(name))))
-            }
-        }
+    /// Switches to a vault, returning its resolved name.
+    pub fn switch(&mut self, name : &str) -> Result<String, error::Error> {
+        let index = self.resolve_vault(name)?;
+        self.vaults.swap(index, 0);
+        Ok(self.vaults[0].0.clone())
     }
 
     /// Lists all vaults to stdout.
@@ -142,42 +327,217 @@ impl Config {
     }
     
     pub fn create_profile(&mut self, name : String, options : args::ListOptions) -> Result<(), error::Error> {
-        if self.profiles.iter().any(|Profile { name : n, options : _ }| n == &name) {
-            Err(error::Error::Generic(format!("A profile by the name {} already exists", format::profile(&name))))
-        }
-        else {
-            self.profiles.push(Profile { name, options });
-            Ok(())
-        }
+        create_profile(&mut self.profiles, name, options)
     }
 
     pub fn get_profile(&self, name : &String) -> Result<&args::ListOptions, error::Error> {
-        self.profiles
-            .iter()
-            .find(|Profile { name : n, options : _ }| n == name)
-            .map(|Profile { name : _, options : o }| o)
+        get_profile(&self.profiles, name)
             .ok_or(error::Error::Generic(format!("No profile by the name {} exists", format::profile(name))))
     }
 
-    pub fn delete_profile(&mut self, name : &String) -> Result<(), error::Error> {
-        match self.profiles.iter().position(|Profile { name : n, options : _ }| n == name) {
-            Some(index) => {
-                let _ = self.profiles.swap_remove(index);
-                Ok(())
+    /// Gets a profile, preferring one scoped to the vault over a global one of the same name.
+    pub fn resolve_profile<'a>(&'a self, name : &String, vault_config : &'a VaultConfig) -> Result<&'a args::ListOptions, error::Error> {
+        match get_profile(&vault_config.profiles, name) {
+            Some(options) => Ok(options),
+            None => self.get_profile(name),
+        }
+    }
+
+    /// Gets the options saved as the default for the list command, if any.
+    pub fn default_profile(&self) -> Option<&args::ListOptions> {
+        self.get_profile(&String::from(DEFAULT_PROFILE)).ok()
+    }
+
+    /// Removes the default options for the list command, returning whether there were any.
+    pub fn clear_default_profile(&mut self) -> bool {
+        let count = self.profiles.len();
+        self.profiles.retain(|Profile { name, options : _ }| name != DEFAULT_PROFILE);
+        self.profiles.len() != count
+    }
+
+    /// Sets the options used by the list command when none are provided, replacing any existing
+    /// default.
+    pub fn set_default_profile(&mut self, options : args::ListOptions) {
+        match self.profiles.iter_mut().find(|Profile { name : n, options : _ }| n == DEFAULT_PROFILE) {
+            Some(profile) => {
+                profile.options = options;
             },
             None => {
-                Err(error::Error::Generic(format!("No profile by the name {} exists", format::profile(name))))
+                self.profiles.push(Profile { name : String::from(DEFAULT_PROFILE), options });
+            }
+        }
+    }
+
+    pub fn delete_profile(&mut self, name : &String) -> Result<(), error::Error> {
+        delete_profile(&mut self.profiles, name)
+    }
+
+    /// Prints the effective configuration, marking which values are defaults.
+    pub fn show(&self, vault_config : Option<&VaultConfig>) {
+        let defaults = Config::default();
+
+        fn value<T : fmt::Display + PartialEq>(value : &T, default : &T) -> String {
+            if value == default {
+                format!("{} (default)", value)
+            }
+            else {
+                value.to_string()
+            }
+        }
+
+        let vaults = self.vaults
+            .iter()
+            .enumerate()
+            .map(|(i, (name, path))| format!("{}{} {}", if i == 0 { "* " } else { "  " }, format::vault(name), path.display()))
+            .collect::<Vec<_>>();
+
+        let vault_profiles = vault_config.map(|c| &c.profiles[..]).unwrap_or(&[]);
+        let mut profiles = vault_profiles
+            .iter()
+            .map(|p| {
+                if self.profiles.iter().any(|g| g.name == p.name) {
+                    format!("{} (vault, overrides global)", format::profile(&p.name))
+                }
+                else {
+                    format!("{} (vault)", format::profile(&p.name))
+                }
+            })
+            .collect::<Vec<_>>();
+        profiles.extend(
+            self.profiles
+                .iter()
+                .filter(|g| !vault_profiles.iter().any(|p| p.name == g.name))
+                .map(|g| format::profile(&g.name).to_string())
+        );
+
+        let mut table = comfy_table::Table::new();
+        table
+            .load_preset(comfy_table::presets::UTF8_FULL)
+            .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
+            .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+        table.set_header(vec!["Setting", "Value"]);
+
+        table.add_row(vec![String::from("Vaults"), if vaults.is_empty() { String::from("(none)") } else { vaults.join("\n") }]);
+        table.add_row(vec![String::from("Editor"), value(&self.editor, &defaults.editor)]);
+        table.add_row(vec![String::from("Info editor"), self.info_editor.clone().unwrap_or_else(|| String::from("(editor)"))]);
+        table.add_row(vec![String::from("Pager"), value(&self.pager, &defaults.pager)]);
+        table.add_row(vec![String::from("Delete mode"), value(&self.delete_mode, &defaults.delete_mode)]);
+        table.add_row(vec![String::from("Tag order"), value(&self.tag_order, &defaults.tag_order)]);
+        table.add_row(vec![String::from("Week start"), value(&self.week_start, &defaults.week_start)]);
+        table.add_row(vec![String::from("Due very close (hours)"), value(&self.due_very_close_hours, &defaults.due_very_close_hours)]);
+        table.add_row(vec![String::from("Due close (days)"), value(&self.due_close_days, &defaults.due_close_days)]);
+        table.add_row(vec![String::from("Score weights"), value(&self.score_weights, &defaults.score_weights)]);
+        let tag_colours : Vec<_> = self.tag_colours
+            .iter()
+            .map(|(tag, colour)| format!("{} {}", format::tag(tag), format::hex(*colour)))
+            .collect();
+        table.add_row(vec![String::from("Tag colours"), if tag_colours.is_empty() { String::from("(none)") } else { tag_colours.join("\n") }]);
+        table.add_row(vec![String::from("Profiles"), if profiles.is_empty() { String::from("(none)") } else { profiles.join("\n") }]);
+
+        println!("{}", table);
+    }
+
+    /// Writes the configuration as TOML, leaving out the vaults unless requested as their paths
+    /// are usually specific to a machine.
+    pub fn export(&self, include_vaults : bool) -> Result<String, error::Error> {
+        let mut value = toml::Value::try_from(self)?;
+
+        if let (false, Some(table)) = (include_vaults, value.as_table_mut()) {
+            table.remove("vaults");
+        }
+
+        Ok(toml::to_string(&value)?)
+    }
+
+    /// Merges in settings exported from another configuration. Settings in the file replace the
+    /// current ones, and profiles which already exist are skipped unless overwriting. Vaults are
+    /// only added if they don't already exist and their folders exist on this machine.
+    pub fn import(&mut self, path : &path::Path, overwrite : bool) -> Result<(), error::Error> {
+        let contents = fs::read_to_string(path)?;
+        let table : toml::value::Table = toml::from_str(&contents)?;
+        let imported : Config = toml::from_str(&contents)?;
+
+        // Settings missing from the file are left alone, rather than reset to their defaults.
+        let present = |key : &str| table.contains_key(key);
+
+        if present("editor") {
+            self.editor = imported.editor;
+        }
+        if present("info_editor") {
+            self.info_editor = imported.info_editor;
+        }
+        if present("pager") {
+            self.pager = imported.pager;
+        }
+        if present("delete_mode") {
+            self.delete_mode = imported.delete_mode;
+        }
+        if present("tag_order") {
+            self.tag_order = imported.tag_order;
+        }
+        if present("week_start") {
+            self.week_start = imported.week_start;
+        }
+        if present("due_very_close_hours") {
+            self.due_very_close_hours = imported.due_very_close_hours;
+        }
+        if present("due_close_days") {
+            self.due_close_days = imported.due_close_days;
+        }
+        if present("score_weights") {
+            self.score_weights = imported.score_weights;
+        }
+        self.tag_colours.extend(imported.tag_colours);
+
+        for profile in imported.profiles {
+            match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+                Some(existing) if overwrite => {
+                    println!("Replaced profile {}", format::profile(&profile.name));
+                    *existing = profile;
+                },
+                Some(_) => {
+                    println!("{} Skipped profile {} as it already exists (use --overwrite to replace it)", format::warning("Warning:"), format::profile(&profile.name));
+                },
+                None => {
+                    println!("Added profile {}", format::profile(&profile.name));
+                    self.profiles.push(profile);
+                }
+            }
+        }
+
+        for (name, vault_path) in imported.vaults {
+            if self.contains_name(&name) || self.contains_path(&vault_path) {
+                println!("{} Skipped vault {} as a vault of that name or path is already set up", format::warning("Warning:"), format::vault(&name));
+            }
+            else if !vault_path.join("state.toml").exists() {
+                println!("{} Skipped vault {} as there is no vault at {} on this machine", format::warning("Warning:"), format::vault(&name), vault_path.display());
+            }
+            else {
+                println!("Added vault {}", format::vault(&name));
+                self.add(name, vault_path);
             }
         }
+
+        Ok(())
+    }
+
+    /// Gets the editor command used for task info.
+    pub fn info_editor(&self) -> &str {
+        self.info_editor.as_deref().unwrap_or(&self.editor)
     }
 
+    /// Lists all profiles to stdout, including those scoped to the current vault if provided.
+    pub fn list_profiles(&self, vault_config : Option<&VaultConfig>) -> Result<(), error::Error> {
+        let vault_profiles = vault_config.map(|c| &c.profiles[..]).unwrap_or(&[]);
 
-    /// Lists all profiles to stdout.
-    pub fn list_profiles(&self) -> Result<(), error::Error> {
-        if self.profiles.is_empty() {
+        if self.profiles.is_empty() && vault_profiles.is_empty() {
             Err(error::Error::Generic(format!("No profiles currently set up, try running: {}", format::command("toru config profile new <NAME> <OPTIONS>"))))
         }
         else {
+            for Profile { name, options : _ } in vault_profiles.iter() {
+                println!("{} (vault)", format::profile(name));
+            }
+
             for Profile { name, options : _ } in self.profiles.iter() {
                 println!("{}", format::profile(name));
             }
@@ -187,7 +547,67 @@ impl Config {
     }
 }
 
+/// Configuration which is specific to a vault, stored within the vault folder.
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct VaultConfig {
+    /// Profiles which are only available in this vault, and take precedence over global profiles.
+    pub profiles : Vec<Profile>,
+}
+
+impl VaultConfig {
+    pub fn load(vault_folder : &path::Path) -> Result<Self, error::Error> {
+        let path = vault_folder.join("config.toml");
+
+        if path.exists() {
+            Ok(toml::from_str(&fs::read_to_string(path)?)?)
+        }
+        else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(self, vault_folder : &path::Path) -> Result<(), error::Error> {
+        Ok(fs::write(vault_folder.join("config.toml"), toml::to_string(&self)?)?)
+    }
 
+    pub fn create_profile(&mut self, name : String, options : args::ListOptions) -> Result<(), error::Error> {
+        create_profile(&mut self.profiles, name, options)
+    }
 
+    pub fn delete_profile(&mut self, name : &String) -> Result<(), error::Error> {
+        delete_profile(&mut self.profiles, name)
+    }
+}
 
+fn create_profile(profiles : &mut Vec<Profile>, name : String, options : args::ListOptions) -> Result<(), error::Error> {
+    if name == DEFAULT_PROFILE {
+        Err(error::Error::Generic(format!("The profile name {} is reserved, use {} instead", format::profile(&name), format::command("toru list <OPTIONS> --save-default"))))
+    }
+    else if profiles.iter().any(|Profile { name : n, options : _ }| n == &name) {
+        Err(error::Error::Generic(format!("A profile by the name {} already exists", format::profile(&name))))
+    }
+    else {
+        profiles.push(Profile { name, options });
+        Ok(())
+    }
+}
+
+fn get_profile<'a>(profiles : &'a [Profile], name : &String) -> Option<&'a args::ListOptions> {
+    profiles
+        .iter()
+        .find(|Profile { name : n, options : _ }| n == name)
+        .map(|Profile { name : _, options : o }| o)
+}
 
+fn delete_profile(profiles : &mut Vec<Profile>, name : &String) -> Result<(), error::Error> {
+    match profiles.iter().position(|Profile { name : n, options : _ }| n == name) {
+        Some(index) => {
+            let _ = profiles.swap_remove(index);
+            Ok(())
+        },
+        None => {
+            Err(error::Error::Generic(format!("No profile by the name {} exists", format::profile(name))))
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::tasks;
+use crate::error;
+use crate::state;
+use crate::graph;
+use crate::config;
+use crate::format;
+use crate::prompt;
+use crate::journal;
+use crate::tasks::Id;
+
+use std::path;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Finds tasks with identical names (and optionally tags), and merges each group into the earliest
+/// created task of the group, after confirmation.
+pub fn dedup(match_tags : bool, dry_run : bool, delete_mode : config::DeleteMode, vault_folder : &path::Path, state : &mut state::State, journal : &mut journal::Journal) -> Result<(), error::Error> {
+
+    let tasks = tasks::Task::load_all(vault_folder, true)?;
+
+    // Group tasks by name, and by their sorted tags if required.
+    let mut groups : BTreeMap<(String, Vec<String>), Vec<&tasks::Task>> = BTreeMap::new();
+    for task in &tasks {
+        let tags = if match_tags {
+            let mut tags : Vec<_> = task.data.tags.iter().cloned().collect();
+            tags.sort();
+            tags
+        }
+        else {
+            Vec::new()
+        };
+
+        groups.entry((task.data.name.clone(), tags)).or_default().push(task);
+    }
+
+    let mut merged_count = 0;
+
+    for ((name, _), mut group) in groups {
+        if group.len() < 2 {
+            continue;
+        }
+
+        group.sort_by_key(|t| t.data.created);
+        let ids : Vec<Id> = group.iter().map(|t| t.data.id).collect();
+        let (keep, redundant) = (ids[0], &ids[1..]);
+
+        println!("Found {} tasks named {} (IDs: [{}])", ids.len(), format::task(&name), format_ids(&ids));
+
+        if dry_run || !prompt::confirm(&format!("Merge into task {}?", format::id(keep)))? {
+            continue;
+        }
+
+        merge(keep, redundant, delete_mode, vault_folder, state, journal)?;
+        merged_count += redundant.len();
+
+        println!("Merged {} tasks into task {}", redundant.len(), format::id(keep));
+    }
+
+    if !dry_run {
+        println!("Removed {} duplicate task{}", merged_count, if merged_count == 1 {""} else {"s"});
+    }
+
+    Ok(())
+}
+
+/// Merges the redundant tasks into the task to keep, relinking any dependents and deleting the
+/// redundant task files.
+fn merge(keep : Id, redundant : &[Id], delete_mode : config::DeleteMode, vault_folder : &path::Path, state : &mut state::State, journal : &mut journal::Journal) -> Result<(), error::Error> {
+
+    let group : HashSet<Id> = redundant.iter().cloned().chain([keep]).collect();
+
+    let mut kept_task = tasks::Task::load(keep, vault_folder, false)?;
+    let mut redundant_tasks = Vec::with_capacity(redundant.len());
+    for id in redundant {
+        redundant_tasks.push(tasks::Task::load(*id, vault_folder, false)?);
+    }
+
+    // Combine the data of all tasks into the kept task.
+    let mut dependencies = kept_task.data.dependencies.clone();
+    for task in &redundant_tasks {
+        kept_task.data.tags.extend(task.data.tags.iter().cloned());
+        kept_task.data.time_entries.extend(task.data.time_entries.iter().cloned());
+        dependencies.extend(task.data.dependencies.iter());
+        if kept_task.data.info.is_none() {
+            kept_task.data.info = task.data.info.clone();
+        }
+    }
+    let dependencies : BTreeSet<Id> = dependencies.difference(&group.iter().cloned().collect()).cloned().collect();
+
+    // Update a copy of the graph, so that nothing is changed if the merge would create a cycle.
+    let mut deps = state.data.deps.clone();
+    let mut dependents = HashSet::new();
+    for id in redundant {
+        let (_, removed_dependents) = deps.remove_node(*id);
+        dependents.extend(removed_dependents);
+    }
+    dependents.remove(&keep);
+
+    for dependency in &kept_task.data.dependencies {
+        deps.remove_edge(keep, *dependency);
+    }
+    for dependency in &dependencies {
+        deps.insert_edge(keep, *dependency)?;
+    }
+    for dependent in &dependents {
+        deps.insert_edge(*dependent, keep)?;
+    }
+
+    if let Some(cycle) = deps.find_cycle() {
+        return Err(error::Error::Generic(format!("Merge aborted due to circular dependency: {}", graph::format_cycle(&cycle))));
+    }
+
+    for id in &group {
+        journal.record(*id)?;
+    }
+
+    state.data.deps = deps;
+    kept_task.data.dependencies = dependencies;
+    state.data.cache.insert(&kept_task);
+    kept_task.save()?;
+
+    for dependent in dependents {
+        journal.record(dependent)?;
+        let mut task = tasks::Task::load(dependent, vault_folder, false)?;
+        task.data.dependencies.retain(|d| !group.contains(d));
+        task.data.dependencies.insert(keep);
+        state.data.cache.insert(&task);
+        task.save()?;
+    }
+
+    // Subtasks of the redundant tasks become subtasks of the kept task, unless they are the kept
+    // task, which takes the parent of the redundant task instead.
+    for id in redundant {
+        let parent = state.data.cache.get(*id).and_then(|t| t.parent);
+        for child in state.data.cache.children(*id) {
+            journal.record(child)?;
+            let mut task = tasks::Task::load(child, vault_folder, false)?;
+            task.data.parent = if child == keep { parent } else { Some(keep) };
+            state.data.cache.insert(&task);
+            task.save()?;
+        }
+    }
+
+    for task in redundant_tasks {
+        state.data.index.remove(task.data.name.clone(), task.data.id);
+        state.data.cache.remove(task.data.id);
+        task.delete(delete_mode, vault_folder)?;
+    }
+
+    Ok(())
+}
+
+fn format_ids(ids : &[Id]) -> String {
+    ids.iter().map(|i| format::id(*i).to_string()).collect::<Vec<_>>().join(", ")
+}
@@ -1,3 +1,4 @@
+use std::io;
 use std::fs;
 use std::mem;
 use std::path;
@@ -8,6 +9,7 @@ use crate::error;
 use crate::graph;
 use crate::state;
 use crate::format;
+use crate::prompt;
 use crate::tasks::Id;
 
 pub fn open_editor(path : &path::Path, editor : &str) -> Result<process::ExitStatus, error::Error> {
@@ -21,7 +23,7 @@ pub fn open_editor(path : &path::Path, editor : &str) -> Result<process::ExitSta
     child.wait().map_err(error::Error::from)
 }
 
-pub fn edit_info(id : Id, vault_folder : path::PathBuf, editor : &str) -> Result<(), error::Error> {
+pub fn edit_info(id : Id, vault_folder : path::PathBuf, editor : &str, state : &mut state::State) -> Result<(), error::Error> {
     let mut task = tasks::Task::load(id, &vault_folder, false)?;
 
     let temp_path = vault_folder.join("temp.md");
@@ -39,14 +41,9 @@ pub fn edit_info(id : Id, vault_folder : path::PathBuf, editor : &str) -> Result
     else {
         let file_contents = fs::read_to_string(&temp_path)?;
 
-        // Check if the remaining file is just whitespace, so the info will become None
-        task.data.info = if file_contents.trim().is_empty() {
-            None
-        }
-        else {
-            Some(file_contents)
-        };
-        
+        task.data.info = info_from(file_contents);
+
+        state.data.cache.insert(&task);
         task.save()?;
 
         // Remove the temporary file
@@ -56,7 +53,139 @@ pub fn edit_info(id : Id, vault_folder : path::PathBuf, editor : &str) -> Result
     }
 }
 
-pub fn edit_raw(id : Id, vault_folder : path::PathBuf, editor : &str, state : &mut state::State) -> Result<(), error::Error> {
+/// Shows the info of a task in the pager, printing it directly if output is not going to a
+/// terminal or the pager could not be launched.
+pub fn open_info(id : Id, vault_folder : &path::Path, pager : &str) -> Result<(), error::Error> {
+    let task = tasks::Task::load(id, vault_folder, true)?;
+
+    let info = match task.data.info {
+        Some(info) => info,
+        None => {
+            println!("Task {} has no info", format::id(id));
+            return Ok(());
+        }
+    };
+
+    if !prompt::is_interactive() {
+        println!("{}", info.trim_end());
+        return Ok(());
+    }
+
+    let temp_path = vault_folder.join("temp.md");
+
+    fs::write(&temp_path, &info)?;
+
+    let status = open_editor(&temp_path, pager);
+
+    fs::remove_file(&temp_path)?;
+
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => {
+            match status.code() {
+                Some(code) => Err(error::Error::Generic(format!("Process responded with a non-zero status code: {}", code))),
+                None => Err(error::Error::Generic(String::from("Process was interrupted by signal"))),
+            }
+        },
+        Err(error::Error::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+            println!("{} Pager {} could not be found", format::warning("Warning:"), format::command(pager));
+            println!("{}", info.trim_end());
+            Ok(())
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// Opens the editor on an empty file to write the info for a new task.
+pub fn new_info(vault_folder : &path::Path, editor : &str) -> Result<Option<String>, error::Error> {
+    let temp_path = vault_folder.join("temp.md");
+
+    fs::write(&temp_path, "")?;
+
+    let status = open_editor(&temp_path, editor)?;
+
+    if !status.success() {
+        fs::remove_file(&temp_path)?;
+        match status.code() {
+            Some(code) => Err(error::Error::Generic(format!("Process responded with a non-zero status code: {}", code))),
+            None => Err(error::Error::Generic(String::from("Process was interrupted by signal"))),
+        }
+    }
+    else {
+        let file_contents = fs::read_to_string(&temp_path)?;
+        fs::remove_file(&temp_path)?;
+
+        Ok(info_from(file_contents))
+    }
+}
+
+/// Gets the info of a task from text, which is None if the text is just whitespace.
+pub fn info_from(contents : String) -> Option<String> {
+    if contents.trim().is_empty() {
+        None
+    }
+    else {
+        Some(contents)
+    }
+}
+
+/// Changes to the fields of a task which can be made without opening an editor.
+pub struct Fields {
+    pub name : Option<String>,
+    pub priority : Option<tasks::Priority>,
+    /// `Some(None)` removes the due date.
+    pub due : Option<Option<chrono::NaiveDateTime>>,
+    pub add_tags : Vec<String>,
+    pub remove_tags : Vec<String>,
+}
+
+impl Fields {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+        && self.priority.is_none()
+        && self.due.is_none()
+        && self.add_tags.is_empty()
+        && self.remove_tags.is_empty()
+    }
+}
+
+/// Applies changes to the fields of a task directly, keeping the index and cache up to date.
+pub fn edit_fields(id : Id, fields : Fields, yes : bool, vault_folder : &path::Path, state : &mut state::State) -> Result<(), error::Error> {
+    let mut task = tasks::Task::load(id, vault_folder, false)?;
+
+    let Fields { name, priority, due, add_tags, remove_tags } = fields;
+
+    if let Some(priority) = priority {
+        task.data.priority = priority;
+    }
+    if let Some(due) = due {
+        if let Some(due) = &due {
+            tasks::confirm_due(due, yes)?;
+        }
+        task.data.due = due;
+    }
+    for tag in remove_tags {
+        task.data.tags.remove(&tag);
+    }
+    task.data.tags.extend(add_tags);
+
+    // Name change means index needs to be updated.
+    if let Some(name) = name {
+        if name != task.data.name {
+            if name.chars().all(|c| c.is_numeric()) {
+                return Err(error::Error::Generic(String::from("Name must not be purely numeric")));
+            }
+            state.data.index.remove(task.data.name.clone(), id);
+            state.data.index.insert(name.clone(), id);
+            task.data.name = name;
+        }
+    }
+
+    state.data.cache.insert(&task);
+    task.save()
+}
+
+pub fn edit_raw(id : Id, yes : bool, vault_folder : path::PathBuf, editor : &str, state : &mut state::State) -> Result<(), error::Error> {
 
     let mut task = tasks::Task::load(id, &vault_folder, false)?;
 
@@ -102,7 +231,27 @@ pub fn edit_raw(id : Id, vault_folder : path::PathBuf, editor : &str, state : &m
                 }
 
                 if let Some(cycle) = state.data.deps.find_cycle() {
-                    return Err(error::Error::Generic(format!("Note edit aborted due to circular dependency: {}", graph::format_cycle(&cycle))));
+                    return Err(error::Error::Generic(format!("Task edit aborted due to circular dependency: {}", graph::format_cycle(&cycle))));
+                }
+            }
+            if edited_task.data.parent != task.data.parent {
+                if let Some(parent) = edited_task.data.parent {
+                    if !state.data.deps.contains_node(parent) {
+                        return Err(error::Error::Generic(format!("No task with an ID of {} exists", format::id(parent))));
+                    }
+                    // Walking up from the new parent, which must not reach the task itself.
+                    let mut ancestor = Some(parent);
+                    while let Some(current) = ancestor {
+                        if current == id {
+                            return Err(error::Error::Generic(format!("Task edit aborted as task {} cannot be a subtask of its own subtask", format::id(id))));
+                        }
+                        ancestor = state.data.cache.get(current).and_then(|t| t.parent);
+                    }
+                }
+            }
+            if edited_task.data.due != task.data.due {
+                if let Some(due) = &edited_task.data.due {
+                    tasks::confirm_due(due, yes)?;
                 }
             }
             // Name change means index needs to be updated.
@@ -114,6 +263,7 @@ pub fn edit_raw(id : Id, vault_folder : path::PathBuf, editor : &str, state : &m
             mem::swap(&mut edited_task.data, &mut task.data);
             mem::drop(edited_task);
 
+            state.data.cache.insert(&task);
             task.save()?;
 
             fs::remove_file(&temp_path)?;
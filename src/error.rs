@@ -11,12 +11,25 @@ pub enum Error {
     Trash(trash::Error),
     TomlDe(toml::de::Error),
     TomlSer(toml::ser::Error),
+    Json(serde_json::Error),
+    Zip(zip::result::ZipError),
     Utf8(str::Utf8Error),
     Fmt(fmt::Error),
     Generic(String),
     Internal(String),
 }
 
+impl Error {
+    /// Exit code for the error, 1 for user errors and 3 for internal or unexpected errors (as 2 is
+    /// used for invalid arguments).
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Generic(_) => 1,
+            _ => 3,
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -25,6 +38,8 @@ impl fmt::Display for Error {
             Error::Trash(err) => write!(f, "{} {}", format::error("Internal Error:"), err),
             Error::TomlDe(err) => write!(f, "{} {}", format::error("Internal Error:"), err),
             Error::TomlSer(err) => write!(f, "{} {}", format::error("Internal Error:"), err),
+            Error::Json(err) => write!(f, "{} {}", format::error("Internal Error:"), err),
+            Error::Zip(err) => write!(f, "{} {}", format::error("Internal Error:"), err),
             Error::Utf8(err) => write!(f, "{} {}", format::error("Internal Error:"), err),
             Error::Fmt(err) => write!(f, "{} {}", format::error("Internal Error:"), err),
             Error::Generic(message) => write!(f, "{} {}", format::error("Error:"), message),
@@ -63,6 +78,18 @@ impl From<toml::ser::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err : serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<zip::result::ZipError> for Error {
+    fn from(err : zip::result::ZipError) -> Self {
+        Error::Zip(err)
+    }
+}
+
 impl From<str::Utf8Error> for Error {
     fn from(err : str::Utf8Error) -> Self {
         Error::Utf8(err)
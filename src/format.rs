@@ -1,11 +1,13 @@
 use crate::tasks;
 use crate::graph;
+use crate::state;
 use crate::error;
 use crate::tasks::Id;
 
-use std::fmt;
 use std::path;
-use std::collections::{HashSet, HashMap};
+use std::sync;
+use std::sync::atomic;
+use std::collections::{BTreeSet, BTreeMap, HashMap};
 use colored::Colorize;
 use chrono::SubsecRound;
 
@@ -25,6 +27,10 @@ pub static FILE : (u8, u8, u8) = (255, 184, 184);
 pub static GREY : (u8, u8, u8) = (99, 110, 114);
 // Pink
 pub static PROFILE : (u8, u8, u8) = (253, 121, 168);
+// Orange
+pub static WARNING : (u8, u8, u8) = (230, 126, 34);
+// Cyan
+pub static HIGHLIGHT : (u8, u8, u8) = (0, 206, 201);
 
 mod due {
     pub static OVERDUE : (u8, u8, u8) = (192, 57, 43);
@@ -40,6 +46,46 @@ pub mod priority {
     pub static BACKLOG : (u8, u8, u8) = (99, 110, 114);
 }
 
+/// Whether output should be coloured, which is the single switch for all colour in the program.
+static COLOUR : atomic::AtomicBool = atomic::AtomicBool::new(true);
+
+/// Hours before a due date at which it is shown as very close, from the configuration.
+static DUE_VERY_CLOSE_HOURS : atomic::AtomicU32 = atomic::AtomicU32::new(24);
+
+/// Days before a due date at which it is shown as close, from the configuration.
+static DUE_CLOSE_DAYS : atomic::AtomicU32 = atomic::AtomicU32::new(5);
+
+/// Colours of tags from the configuration, set once at startup.
+static TAG_COLOURS : sync::RwLock<BTreeMap<String, (u8, u8, u8)>> = sync::RwLock::new(BTreeMap::new());
+
+/// Enables or disables colour (and other styling) for all output. When enabled, colour is still
+/// left out if the output is not a terminal.
+pub fn set_colour(enabled : bool) {
+    COLOUR.store(enabled, atomic::Ordering::Relaxed);
+    if !enabled {
+        colored::control::set_override(false);
+    }
+}
+
+fn colour_enabled() -> bool {
+    COLOUR.load(atomic::Ordering::Relaxed)
+}
+
+/// Sets the colours which tags are displayed in.
+pub fn set_tag_colours(colours : BTreeMap<String, (u8, u8, u8)>) {
+    *TAG_COLOURS.write().unwrap() = colours;
+}
+
+/// Sets how close to a due date it is shown as very close and close.
+pub fn set_due_thresholds(very_close_hours : u32, close_days : u32) {
+    DUE_VERY_CLOSE_HOURS.store(very_close_hours, atomic::Ordering::Relaxed);
+    DUE_CLOSE_DAYS.store(close_days, atomic::Ordering::Relaxed);
+}
+
+fn tag_colour(tag : &str) -> Option<(u8, u8, u8)> {
+    TAG_COLOURS.read().unwrap().get(tag).copied()
+}
+
 fn text(string : &str, colour : (u8, u8, u8)) -> colored::ColoredString {
     string.truecolor(colour.0, colour.1, colour.2)
 }
@@ -56,6 +102,10 @@ pub fn error(string : &str) -> colored::ColoredString {
     text(string, ERROR).bold()
 }
 
+pub fn warning(string : &str) -> colored::ColoredString {
+    text(string, WARNING).bold()
+}
+
 pub fn command(string : &str) -> colored::ColoredString {
     text(string, COMMAND).bold()
 }
@@ -68,6 +118,10 @@ pub fn file(string : &str) -> colored::ColoredString {
     text(string, FILE).bold()
 }
 
+pub fn highlight(string : &str) -> colored::ColoredString {
+    text(string, HIGHLIGHT).bold().underline()
+}
+
 pub fn greyed_out(string : &str) -> colored::ColoredString {
     text(string, GREY)
 }
@@ -76,84 +130,186 @@ pub fn profile(string : &str) -> colored::ColoredString {
     text(string, PROFILE)
 }
 
-pub fn priority(priority : &tasks::Priority) -> String {
+/// The name and colour of a priority, which every way of displaying a priority goes through so
+/// that each level is only handled in one place.
+fn priority_style(priority : &tasks::Priority) -> (&'static str, (u8, u8, u8)) {
     use tasks::Priority::*;
-    let priority = match priority {
-        Backlog => text("backlog", priority::BACKLOG),
-        Low => text("low", priority::LOW),
-        Medium => text("medium", priority::MEDIUM),
-        High => text("high", priority::HIGH),
-    };
-    format!("{}", priority)
+    match priority {
+        Backlog => ("backlog", priority::BACKLOG),
+        Low => ("low", priority::LOW),
+        Medium => ("medium", priority::MEDIUM),
+        High => ("high", priority::HIGH),
+    }
+}
+
+/// The name of a priority without colour, as used in CSV output.
+pub fn priority_name(priority : &tasks::Priority) -> &'static str {
+    priority_style(priority).0
+}
+
+pub fn priority(priority : &tasks::Priority) -> String {
+    let (name, colour) = priority_style(priority);
+    format!("{}", text(name, colour))
 }
 
-pub fn hash_set<T : fmt::Display>(set : &HashSet<T>) -> Result<String, error::Error> {
-    let mut output = String::new();
+/// A coloured dot representing the priority, for compact displays.
+pub fn priority_dot(priority : &tasks::Priority) -> colored::ColoredString {
+    text("●", priority_style(priority).1)
+}
 
-    for value in set.iter() {
-        fmt::write(&mut output, format_args!("{}, ", value))?;
+/// A tag in its configured colour, if it has one.
+pub fn tag(tag : &str) -> colored::ColoredString {
+    match tag_colour(tag) {
+        Some(colour) => text(tag, colour),
+        None => tag.normal(),
     }
+}
 
-    // Remove the trailing comma and space.
-    if !output.is_empty() {
-        output.pop();
-        output.pop();
+/// A colour as hex, shown in that colour.
+pub fn hex(colour : (u8, u8, u8)) -> colored::ColoredString {
+    text(&format!("#{:02x}{:02x}{:02x}", colour.0, colour.1, colour.2), colour)
+}
+
+/// Orders a set of tags, alphabetically or with the most frequent first if frequencies across the
+/// vault are provided.
+fn ordered_tags<'a>(tags : &'a BTreeSet<String>, frequencies : Option<&HashMap<String, usize>>) -> Vec<&'a String> {
+    let mut tags : Vec<_> = tags.iter().collect();
+
+    if let Some(frequencies) = frequencies {
+        // Stable sort, so ties stay alphabetical.
+        tags.sort_by(|t1, t2| frequencies.get(*t2).cmp(&frequencies.get(*t1)));
     }
 
-    Ok(output)
+    tags
 }
 
-pub fn due_date(due : &chrono::NaiveDateTime, include_fuzzy_period : bool) -> String {
+/// Quotes a CSV field if it contains characters which would otherwise break the row.
+pub fn csv_field(value : &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    }
+    else {
+        String::from(value)
+    }
+}
 
-    let remaining = *due - chrono::Local::now().naive_local();
+/// Formats a set of tags without colour, ordered as by `ordered_tags`.
+pub fn tags(tags : &BTreeSet<String>, frequencies : Option<&HashMap<String, usize>>) -> String {
+    ordered_tags(tags, frequencies)
+        .iter()
+        .map(|t| t.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Formats a set of tags, each in its configured colour.
+pub fn coloured_tags(tags : &BTreeSet<String>, frequencies : Option<&HashMap<String, usize>>) -> String {
+    ordered_tags(tags, frequencies)
+        .iter()
+        .map(|t| self::tag(t).to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    let fuzzy_period = if remaining.num_days() != 0 {
-        let days = remaining.num_days().abs();
+/// Describes how tracked time compares to an estimate.
+pub fn over_under(estimate : tasks::Duration, tracked : tasks::Duration) -> String {
+    let (estimate, tracked) = (estimate.total_minutes(), tracked.total_minutes());
+
+    if tracked > estimate {
+        format!("{} over", tasks::Duration::from_minutes(tracked - estimate))
+    }
+    else if tracked < estimate {
+        format!("{} under", tasks::Duration::from_minutes(estimate - tracked))
+    }
+    else {
+        String::from("on estimate")
+    }
+}
+
+/// Formats a duration as a rough period in its largest unit (e.g. "3 days"), ignoring sign.
+pub fn fuzzy_period(period : chrono::Duration) -> String {
+    if period.num_days() != 0 {
+        let days = period.num_days().abs();
         format!("{} day{}", days, if days == 1 {""} else {"s"})
     }
-    else if remaining.num_hours() != 0 {
-        let hours = remaining.num_hours().abs();
+    else if period.num_hours() != 0 {
+        let hours = period.num_hours().abs();
         format!("{} hour{}", hours, if hours == 1 {""} else {"s"})
     }
-    else if remaining.num_minutes() != 0 {
-        let minutes = remaining.num_minutes().abs();
+    else if period.num_minutes() != 0 {
+        let minutes = period.num_minutes().abs();
         format!("{} minute{}", minutes, if minutes == 1 {""} else {"s"})
     }
     else {
-        let seconds = remaining.num_seconds().abs();
+        let seconds = period.num_seconds().abs();
         format!("{} second{}", seconds, if seconds == 1 {""} else {"s"})
-    };
+    }
+}
 
-    if include_fuzzy_period {
-        if remaining < chrono::Duration::zero() {
-            format!("{} {}", due.round_subsecs(0), text(&format!("({} overdue)", fuzzy_period), due::OVERDUE))
-        }
-        else if remaining < chrono::Duration::days(1) {
-            format!("{} {}", due.round_subsecs(0), text(&format!("({} remaining)", fuzzy_period), due::VERY_CLOSE))
+/// Describes the time remaining until a due date (or how long it is overdue), along with the
+/// colour for how close it is.
+fn due_remaining(due : &chrono::NaiveDateTime) -> (String, (u8, u8, u8)) {
+    let remaining = *due - chrono::Local::now().naive_local();
 
-        }
-        else if remaining < chrono::Duration::days(5) {
-            format!("{} {}", due.round_subsecs(0), text(&format!("({} remaining)", fuzzy_period), due::CLOSE))
+    let fuzzy_period = fuzzy_period(remaining);
 
-        }
-        else {
-            format!("{} {}", due.round_subsecs(0), text(&format!("({} remaining)", fuzzy_period), due::PLENTY_OF_TIME))
-        }
+    let very_close = chrono::Duration::hours(i64::from(DUE_VERY_CLOSE_HOURS.load(atomic::Ordering::Relaxed)));
+    let close = chrono::Duration::days(i64::from(DUE_CLOSE_DAYS.load(atomic::Ordering::Relaxed)));
+
+    if remaining < chrono::Duration::zero() {
+        (format!("({} overdue)", fuzzy_period), due::OVERDUE)
+    }
+    else if remaining < very_close {
+        (format!("({} remaining)", fuzzy_period), due::VERY_CLOSE)
+    }
+    else if remaining < close {
+        (format!("({} remaining)", fuzzy_period), due::CLOSE)
+    }
+    else {
+        (format!("({} remaining)", fuzzy_period), due::PLENTY_OF_TIME)
+    }
+}
+
+pub fn due_date(due : &chrono::NaiveDateTime, include_fuzzy_period : bool) -> String {
+    if include_fuzzy_period {
+        let (remaining, colour) = due_remaining(due);
+        format!("{} {}", due.round_subsecs(0), text(&remaining, colour))
     }
     else {
         format!("{}", due.round_subsecs(0))
     }
 }
 
-pub fn dependencies(start : Id, vault_folder : &path::Path, graph : &graph::Graph) -> Result<(), error::Error> {
+pub fn dependencies(start : Id, vault_folder : &path::Path, graph : &graph::Graph, detailed : bool) -> Result<(), error::Error> {
+    let tasks = tasks::Task::load_all_as_map(vault_folder, true)?;
+
+    tree(start, &|id| graph.edges.get(&id).into_iter().flatten().copied().collect(), &tasks, detailed)
+}
 
-    pub fn helper(curr : Id, prefix : &String, is_last_item : bool, graph : &graph::Graph, tasks : &HashMap<Id, tasks::Task>) -> Result<(), error::Error> {
+pub fn subtasks(start : Id, vault_folder : &path::Path, cache : &state::Cache, detailed : bool) -> Result<(), error::Error> {
+    let tasks = tasks::Task::load_all_as_map(vault_folder, true)?;
 
-        let next = graph.edges.get(&curr).unwrap();
+    tree(start, &|id| cache.children(id), &tasks, detailed)
+}
 
-        {
-            let task = tasks.get(&curr).unwrap();
+/// Prints a tree of tasks starting from the given task, where `next` gets the children of a task.
+/// Tasks which can't be found (such as from a manually deleted file) are shown as missing.
+fn tree(start : Id, next : &dyn Fn(Id) -> Vec<Id>, tasks : &HashMap<Id, tasks::Task>, detailed : bool) -> Result<(), error::Error> {
 
+    pub fn helper(curr : Id, prefix : &String, is_last_item : bool, next : &dyn Fn(Id) -> Vec<Id>, tasks : &HashMap<Id, tasks::Task>, detailed : bool) -> Result<(), error::Error> {
+
+        let next_nodes = next(curr);
+
+        let task = match tasks.get(&curr) {
+            Some(task) => task,
+            None => {
+                let branch = if is_last_item { "└──" } else { "├──" };
+                println!("{}{}{}", prefix, branch, self::warning(&format!("(missing id {})", curr)));
+                return Ok(());
+            }
+        };
+
+        {
             let name = if task.data.completed.is_some() {
                 self::greyed_out(&task.data.name)
             }
@@ -161,17 +317,28 @@ pub fn dependencies(start : Id, vault_folder : &path::Path, graph : &graph::Grap
                 self::task(&task.data.name)
             };
 
+            // Priority and due date annotations for the detailed view.
+            let details = if detailed {
+                match task.data.due {
+                    Some(due) => format!(" {} {}", self::priority_dot(&task.data.priority), self::due_date(&due, task.data.completed.is_none())),
+                    None => format!(" {}", self::priority_dot(&task.data.priority)),
+                }
+            }
+            else {
+                String::new()
+            };
+
             if is_last_item {
-                println!("{}└──{} (ID: {})", prefix, name, self::id(curr))
+                println!("{}└──{} (ID: {}){}", prefix, name, self::id(curr), details)
             }
             else {
-                println!("{}├──{} (ID: {})", prefix, name, self::id(curr))
+                println!("{}├──{} (ID: {}){}", prefix, name, self::id(curr), details)
             }
         }
 
-        let count = next.len();
+        let count = next_nodes.len();
 
-        for (i, node) in next.iter().enumerate() {
+        for (i, node) in next_nodes.iter().enumerate() {
             let new_is_last_item = i == count - 1;
 
             let new_prefix = if is_last_item {
@@ -181,15 +348,13 @@ pub fn dependencies(start : Id, vault_folder : &path::Path, graph : &graph::Grap
                 format!("{}│  ", prefix)
             };
 
-            helper(*node, &new_prefix, new_is_last_item, graph, tasks)?;
+            helper(*node, &new_prefix, new_is_last_item, next, tasks, detailed)?;
         }
 
         Ok(())
     }
 
-    let tasks = tasks::Task::load_all_as_map(vault_folder, true)?;
-
-    helper(start, &String::new(), true, graph, &tasks)
+    helper(start, &String::new(), true, next, tasks, detailed)
 }
 
 
@@ -199,63 +364,159 @@ pub fn dependencies(start : Id, vault_folder : &path::Path, graph : &graph::Grap
 pub mod cell {
     use crate::tasks;
 
+    use std::collections::{BTreeSet, HashMap};
     use chrono::SubsecRound;
 
     fn cell<T : Into<comfy_table::Cell>>(text : T, colour : (u8, u8, u8)) -> comfy_table::Cell {
-        text.into().fg(comfy_table::Color::from(colour))
+        if super::colour_enabled() {
+            text.into().fg(comfy_table::Color::from(colour))
+        }
+        else {
+            text.into()
+        }
+    }
+
+    /// A set of tags, each in its configured colour.
+    pub fn tags(tags : &BTreeSet<String>, frequencies : Option<&HashMap<String, usize>>) -> comfy_table::Cell {
+        comfy_table::Cell::new(super::coloured_tags(tags, frequencies))
     }
 
     pub fn priority(priority : &tasks::Priority) -> comfy_table::Cell {
-        use tasks::Priority::*;
-        match priority {
-            Backlog => comfy_table::Cell::new("backlog").fg(comfy_table::Color::from(super::priority::BACKLOG)),
-            Low => comfy_table::Cell::new("low").fg(comfy_table::Color::from(super::priority::LOW)),
-            Medium => comfy_table::Cell::new("medium").fg(comfy_table::Color::from(super::priority::MEDIUM)),
-            High => comfy_table::Cell::new("high").fg(comfy_table::Color::from(super::priority::HIGH)),
-        }
+        let (name, colour) = super::priority_style(priority);
+        cell(name, colour)
+    }
+
+    /// A task name, coloured according to the priority of the task.
+    pub fn task_name(name : &str, priority : &tasks::Priority) -> comfy_table::Cell {
+        cell(name, super::priority_style(priority).1)
     }
 
     pub fn due_date(due : &chrono::NaiveDateTime, include_fuzzy_period : bool) -> comfy_table::Cell {
+        if include_fuzzy_period {
+            let (remaining, colour) = super::due_remaining(due);
+            cell(format!("{} {}", due.round_subsecs(0), remaining), colour)
+        }
+        else {
+            comfy_table::Cell::new(format!("{}", due.round_subsecs(0)))
+        }
+    }
+}
+
 
-        let remaining = *due - chrono::Local::now().naive_local();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let fuzzy_period = if remaining.num_days() != 0 {
-            let days = remaining.num_days().abs();
-            format!("{} day{}", days, if days == 1 {""} else {"s"})
+    /// Held by tests which change the global colour settings, so that they don't run at the same
+    /// time, restoring the defaults when dropped.
+    struct ColourGuard {
+        _lock : sync::MutexGuard<'static, ()>,
+    }
+
+    impl ColourGuard {
+        fn new() -> Self {
+            static LOCK : sync::Mutex<()> = sync::Mutex::new(());
+            // A failed test still restores the defaults, so a poisoned lock can be reused.
+            Self { _lock : LOCK.lock().unwrap_or_else(|e| e.into_inner()) }
         }
-        else if remaining.num_hours() != 0 {
-            let hours = remaining.num_hours().abs();
-            format!("{} hour{}", hours, if hours == 1 {""} else {"s"})
+    }
+
+    impl Drop for ColourGuard {
+        fn drop(&mut self) {
+            set_colour(true);
+            colored::control::unset_override();
+            set_tag_colours(BTreeMap::new());
         }
-        else if remaining.num_minutes() != 0 {
-            let minutes = remaining.num_minutes().abs();
-            format!("{} minute{}", minutes, if minutes == 1 {""} else {"s"})
+    }
+
+    #[test]
+    fn no_escape_sequences_when_colour_disabled() {
+        let _guard = ColourGuard::new();
+
+        // Forcing colour on first, so that disabling it is what removes the escape sequences.
+        colored::control::set_override(true);
+        set_colour(false);
+        set_tag_colours(BTreeMap::from([(String::from("work"), (1, 2, 3))]));
+
+        let due = chrono::Local::now().naive_local() + chrono::Duration::hours(2);
+        let tags = BTreeSet::from([String::from("work"), String::from("home")]);
+
+        let mut table = comfy_table::Table::new();
+        table
+            .enforce_styling()
+            .set_header(vec!["Priority", "Name", "Due", "Tags"])
+            .add_row(vec![
+                cell::priority(&tasks::Priority::High),
+                cell::task_name("task", &tasks::Priority::Low),
+                cell::due_date(&due, true),
+                cell::tags(&tags, None),
+            ]);
+
+        let outputs = [
+            id(3).to_string(),
+            error("Error:").to_string(),
+            warning("Warning:").to_string(),
+            task("task").to_string(),
+            highlight("text").to_string(),
+            priority(&tasks::Priority::Medium),
+            priority_dot(&tasks::Priority::Backlog).to_string(),
+            due_date(&due, true),
+            coloured_tags(&tags, None),
+            table.to_string(),
+        ];
+
+        for output in outputs {
+            assert!(!output.contains("\x1B["), "{:?} contains an escape sequence", output);
         }
-        else {
-            let seconds = remaining.num_seconds().abs();
-            format!("{} second{}", seconds, if seconds == 1 {""} else {"s"})
-        };
+    }
 
-        if include_fuzzy_period {
-            if remaining < chrono::Duration::zero() {
-                cell(format!("{} {}", due.round_subsecs(0), format!("({} overdue)", fuzzy_period)), super::due::OVERDUE)
-            }
-            else if remaining < chrono::Duration::days(1) {
-                cell(format!("{} {}", due.round_subsecs(0), format!("({} remaining)", fuzzy_period)), super::due::VERY_CLOSE)
+    #[test]
+    fn tag_cells_colour_each_tag() {
+        let _guard = ColourGuard::new();
 
-            }
-            else if remaining < chrono::Duration::days(5) {
-                cell(format!("{} {}", due.round_subsecs(0), format!("({} remaining)", fuzzy_period)), super::due::CLOSE)
+        colored::control::set_override(true);
+        set_tag_colours(BTreeMap::from([(String::from("work"), (1, 2, 3))]));
 
-            }
-            else {
-                cell(format!("{} {}", due.round_subsecs(0), format!("({} remaining)", fuzzy_period)), super::due::PLENTY_OF_TIME)
-            }
-        }
-        else {
-            comfy_table::Cell::new(format!("{}", due.round_subsecs(0)))
+        let tags = BTreeSet::from([String::from("home"), String::from("work")]);
+        let content = cell::tags(&tags, None).content();
+
+        assert_eq!(content, format!("home, {}", "work".truecolor(1, 2, 3)));
+    }
+
+    #[test]
+    fn trees_show_missing_tasks() {
+        let vault = crate::testing::TempVault::new();
+        let mut state = vault.state();
+        let missing = vault.new_task("missing", &mut state);
+        let parent = vault.new_task_with(tasks::NewTask { name : String::from("parent"), dependencies : vec![missing], ..Default::default() }, &mut state);
+        vault.new_task_with(tasks::NewTask { name : String::from("child"), parent : Some(missing), ..Default::default() }, &mut state);
+        std::fs::remove_file(vault.path.join("tasks").join(format!("{}.toml", missing))).unwrap();
+
+        // The graph and cache still refer to the deleted task.
+        assert!(dependencies(parent, &vault.path, &state.data.deps, true).is_ok());
+        assert!(dependencies(missing, &vault.path, &state.data.deps, false).is_ok());
+        assert!(subtasks(missing, &vault.path, &state.data.cache, true).is_ok());
+    }
+
+    #[test]
+    fn every_priority_is_formatted() {
+        use clap::ValueEnum;
+
+        let mut names = BTreeSet::new();
+
+        for level in tasks::Priority::value_variants() {
+            let name = priority_name(level);
+            assert!(!name.is_empty());
+            assert!(names.insert(name), "{} is used for more than one priority", name);
+
+            // Matching the value accepted on the command line.
+            assert_eq!(level.to_possible_value().unwrap().get_name(), name);
+
+            assert!(priority(level).contains(name));
+            assert_eq!(cell::priority(level).content(), name);
+            assert!(!priority_dot(level).to_string().is_empty());
         }
 
+        assert_eq!(names.len(), 4);
     }
 }
-
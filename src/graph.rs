@@ -4,7 +4,7 @@ use crate::format;
 use crate::tasks::Id;
 
 use std::fmt::Write;
-use std::collections::{HashSet, BTreeSet, BTreeMap};
+use std::collections::{HashSet, HashMap, BTreeSet, BTreeMap};
 use serde_with::{serde_as, DisplayFromStr};
 
 #[serde_as]
@@ -15,6 +15,8 @@ pub struct Graph {
 }
 
 impl Graph {
+    /// Creates the graph from the dependencies of each task. Dependencies on tasks which don't
+    /// exist are left out with a warning, so every edge of the graph is between existing nodes.
     pub fn create(tasks : Vec<tasks::Task>) -> Self {
         let mut edges = BTreeMap::new();
 
@@ -22,6 +24,14 @@ impl Graph {
             edges.insert(task.data.id, task.data.dependencies);
         }
 
+        let nodes : HashSet<Id> = edges.keys().copied().collect();
+        for (id, outgoing) in &mut edges {
+            for missing in outgoing.iter().filter(|d| !nodes.contains(d)) {
+                println!("{} Task {} depends on missing task {}, which was ignored (run {} to remove it)", format::warning("Warning:"), format::id(*id), format::id(*missing), format::command("toru vault check --fix"));
+            }
+            outgoing.retain(|d| nodes.contains(d));
+        }
+
         Self {
             edges
         }
@@ -40,7 +50,7 @@ impl Graph {
             Err(error::Error::Internal(String::from("Attempt to insert an edge in the dependency graph with a node which wasn't present")))
         }
         else if first == second {
-            Err(error::Error::Generic(format!("Note with ID {} cannot depend on itself", format::id(first))))
+            Err(error::Error::Generic(format!("Task with ID {} cannot depend on itself", format::id(first))))
         }
         else {
             let outgoing = self.edges.get_mut(&first).unwrap();
@@ -66,6 +76,15 @@ impl Graph {
         }
     }
 
+    /// Gets the tasks which directly depend on the given task.
+    pub fn dependents(&self, node : Id) -> HashSet<Id> {
+        self.edges
+            .iter()
+            .filter(|(_, outgoing)| outgoing.contains(&node))
+            .map(|(&dependent, _)| dependent)
+            .collect()
+    }
+
     pub fn remove_edge(&mut self, first : Id, second : Id) -> bool {
         match self.edges.get_mut(&first) {
             Some(outgoing) => {
@@ -102,7 +121,7 @@ impl Graph {
         while !unvisited.is_empty() {
             let start = unvisited.iter().next().unwrap();
 
-            let result = self.find_cycle_local(*start, &mut unvisited, &mut HashSet::new());
+            let result = self.find_cycle_local(*start, &mut unvisited, &mut Vec::new());
             if result.is_some() {
                 return result;
             }
@@ -111,45 +130,112 @@ impl Graph {
         None
     }
 
-    /// Traverses a notes dependencies to get the set of all dependencies, direct and indirect.
-    pub fn get_nested_deps(&self, id : Id) -> HashSet<Id> {
-        fn helper(graph : &Graph, curr : &Id, output : &mut HashSet<Id>) {
-            for dep in graph.edges.get(curr).unwrap() {
-                output.insert(*dep);
-                helper(graph, dep, output)
+    /// Traverses a task's dependencies to get the set of all dependencies, direct and indirect.
+    /// Errors if a cycle is reached, which can only happen if the state file was edited by hand.
+    pub fn get_nested_deps(&self, id : Id) -> Result<HashSet<Id>, error::Error> {
+        fn helper(graph : &Graph, curr : Id, path : &mut Vec<Id>, output : &mut HashSet<Id>) -> Result<(), error::Error> {
+            path.push(curr);
+            for dep in graph.edges.get(&curr).into_iter().flatten() {
+                if let Some(start) = path.iter().position(|n| n == dep) {
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(*dep);
+                    return Err(error::Error::Generic(format!("The dependency graph contains a cycle, the state file may be corrupt: {}", format_cycle(&cycle))));
+                }
+                // Already visited nodes have had their dependencies added.
+                if output.insert(*dep) {
+                    helper(graph, *dep, path, output)?;
+                }
             }
+            path.pop();
+
+            Ok(())
         }
 
         let mut output = HashSet::new();
-        helper(self, &id, &mut output);
+        helper(self, id, &mut Vec::new(), &mut output)?;
+
+        Ok(output)
+    }
+
+    /// Gets a node along with all of its nested dependencies, ordered such that each node comes
+    /// after all of its dependencies. Assumes the graph contains no cycles.
+    pub fn topological_subtree(&self, id : Id) -> Vec<Id> {
+        fn helper(graph : &Graph, curr : Id, visited : &mut HashSet<Id>, output : &mut Vec<Id>) {
+            if visited.insert(curr) {
+                for dep in graph.edges.get(&curr).into_iter().flatten() {
+                    helper(graph, *dep, visited, output);
+                }
+                output.push(curr);
+            }
+        }
+
+        let mut output = Vec::new();
+        helper(self, id, &mut HashSet::new(), &mut output);
 
         output
     }
 
-    fn find_cycle_local(&self, start : Id, unvisited : &mut BTreeSet<Id>, current_path_visited : &mut HashSet<Id>) -> Option<Vec<Id>> {
+    /// Renders the graph in the Graphviz DOT format, with edges from each task to its dependencies
+    /// and completed tasks in grey. Nodes without a corresponding task are skipped.
+    pub fn to_dot(&self, tasks : &HashMap<Id, tasks::Task>) -> String {
+        let (r, g, b) = format::GREY;
+        let grey = format!("#{:02x}{:02x}{:02x}", r, g, b);
 
-        // If already visited in the current path, then there is a cycle
-        if current_path_visited.contains(&start) {
-            Some(vec![start])
-        }
-        else {
-            unvisited.remove(&start);
-            current_path_visited.insert(start);
-
-            // Iterate over the outgoing edges
-            for node in self.edges.get(&start).unwrap() {
-                let result = self.find_cycle_local(*node, unvisited, current_path_visited);
-                if let Some(mut path) = result {
-                    path.push(start);
-                    return Some(path);
-                }
-                // Remove the searched node from the current_path_visited set because already
-                // reached full search depth on it.
-                current_path_visited.remove(node);
+        let mut dot = String::from("digraph tasks {\n");
+
+        for (id, outgoing) in &self.edges {
+            let task = match tasks.get(id) {
+                Some(task) => task,
+                None => continue,
+            };
+
+            let label = format!("{}: {}", id, task.data.name).replace('\\', "\\\\").replace('"', "\\\"");
+            if task.data.completed.is_some() {
+                writeln!(&mut dot, "    {} [label=\"{}\", color=\"{}\", fontcolor=\"{}\"];", id, label, grey, grey).unwrap();
+            }
+            else {
+                writeln!(&mut dot, "    {} [label=\"{}\"];", id, label).unwrap();
             }
 
-            None
+            for dependency in outgoing.iter().filter(|d| tasks.contains_key(d)) {
+                writeln!(&mut dot, "    {} -> {};", id, dependency).unwrap();
+            }
         }
+
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    /// Depth first search for a cycle, where `path` is the recursion stack. A returned cycle
+    /// contains only the nodes on it, in dependency order, starting and ending with the same node.
+    fn find_cycle_local(&self, start : Id, unvisited : &mut BTreeSet<Id>, path : &mut Vec<Id>) -> Option<Vec<Id>> {
+
+        // If already on the current path, then there is a cycle from that point.
+        if let Some(position) = path.iter().position(|n| *n == start) {
+            let mut cycle = path[position..].to_vec();
+            cycle.push(start);
+            return Some(cycle);
+        }
+
+        // Nodes which were already fully searched cannot lead to a cycle.
+        if !unvisited.remove(&start) {
+            return None;
+        }
+
+        path.push(start);
+
+        // Iterate over the outgoing edges
+        for node in self.edges.get(&start).into_iter().flatten() {
+            let result = self.find_cycle_local(*node, unvisited, path);
+            if result.is_some() {
+                return result;
+            }
+        }
+
+        path.pop();
+
+        None
     }
 }
 
@@ -166,3 +252,94 @@ pub fn format_cycle(cycle : &Vec<Id>) -> String {
 
     formatted
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a graph from each node and the nodes it depends on.
+    fn graph(edges : &[(Id, &[Id])]) -> Graph {
+        Graph {
+            edges : edges.iter().map(|(node, outgoing)| (*node, outgoing.iter().copied().collect())).collect(),
+        }
+    }
+
+    #[test]
+    fn nested_deps_include_indirect_dependencies() {
+        let graph = graph(&[(1, &[2]), (2, &[3]), (3, &[]), (4, &[1])]);
+
+        assert_eq!(graph.get_nested_deps(1).unwrap(), HashSet::from([2, 3]));
+        assert_eq!(graph.get_nested_deps(3).unwrap(), HashSet::new());
+    }
+
+    #[test]
+    fn nested_deps_visit_shared_dependencies_once() {
+        let graph = graph(&[(1, &[2, 3]), (2, &[4]), (3, &[4]), (4, &[])]);
+
+        assert_eq!(graph.get_nested_deps(1).unwrap(), HashSet::from([2, 3, 4]));
+    }
+
+    #[test]
+    fn nested_deps_error_on_two_node_cycle() {
+        let graph = graph(&[(1, &[2]), (2, &[1])]);
+
+        assert!(graph.get_nested_deps(1).is_err());
+        assert!(graph.get_nested_deps(2).is_err());
+    }
+
+    #[test]
+    fn nested_deps_error_on_cycle_below_node() {
+        let graph = graph(&[(1, &[2]), (2, &[3]), (3, &[2])]);
+
+        assert!(graph.get_nested_deps(1).is_err());
+    }
+
+    #[test]
+    fn no_cycle_in_acyclic_graph() {
+        let graph = graph(&[(1, &[2, 3]), (2, &[4]), (3, &[4]), (4, &[])]);
+
+        assert_eq!(graph.find_cycle(), None);
+    }
+
+    #[test]
+    fn three_cycle_is_reported_in_dependency_order() {
+        let graph = graph(&[(1, &[2]), (2, &[3]), (3, &[1])]);
+
+        assert_eq!(graph.find_cycle(), Some(vec![1, 2, 3, 1]));
+    }
+
+    #[test]
+    fn cycle_after_long_prefix_excludes_the_prefix() {
+        let graph = graph(&[(1, &[2]), (2, &[3]), (3, &[4]), (4, &[5]), (5, &[6]), (6, &[7]), (7, &[5])]);
+
+        assert_eq!(graph.find_cycle(), Some(vec![5, 6, 7, 5]));
+    }
+
+    #[test]
+    fn cycle_excludes_previously_searched_branches() {
+        let graph = graph(&[(1, &[2, 3]), (2, &[]), (3, &[4]), (4, &[3])]);
+
+        assert_eq!(graph.find_cycle(), Some(vec![3, 4, 3]));
+    }
+
+    #[test]
+    fn cycle_found_in_separate_component() {
+        let graph = graph(&[(1, &[2]), (2, &[]), (3, &[4]), (4, &[3])]);
+
+        assert_eq!(graph.find_cycle(), Some(vec![3, 4, 3]));
+    }
+
+    #[test]
+    fn create_leaves_out_dependencies_on_missing_tasks() {
+        let vault = crate::testing::TempVault::new();
+        let mut state = vault.state();
+        let missing = vault.new_task("missing", &mut state);
+        let dependent = vault.new_task_with(tasks::NewTask { name : String::from("dependent"), dependencies : vec![missing], ..Default::default() }, &mut state);
+        std::fs::remove_file(vault.path.join("tasks").join(format!("{}.toml", missing))).unwrap();
+
+        let graph = Graph::create(tasks::Task::load_all(&vault.path, true).unwrap());
+
+        assert!(!graph.contains_node(missing));
+        assert_eq!(graph.edges.get(&dependent), Some(&BTreeSet::new()));
+    }
+}
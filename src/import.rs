@@ -0,0 +1,171 @@
+use crate::args;
+use crate::error;
+use crate::tasks;
+use crate::state;
+use crate::format;
+use crate::journal;
+use crate::tasks::Id;
+
+use std::fs;
+use std::path;
+use std::collections::HashMap;
+
+/// A task as it appears in an import file.
+#[derive(serde::Deserialize)]
+struct Entry {
+    name : String,
+    #[serde(default)]
+    info : Option<String>,
+    #[serde(default)]
+    tags : Vec<String>,
+    #[serde(default)]
+    priority : Option<String>,
+    #[serde(default)]
+    due : Option<String>,
+    /// Tasks by ID, or by name (either from the same import or already in the vault).
+    #[serde(default)]
+    dependencies : Vec<Dependency>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Dependency {
+    Id(Id),
+    Name(String),
+}
+
+/// An entry which has been validated, but not yet created.
+struct Pending {
+    position : usize,
+    entry : Entry,
+    priority : Option<tasks::Priority>,
+    due : Option<chrono::NaiveDateTime>,
+}
+
+/// Creates tasks from a file containing an array of task objects. Malformed entries are skipped
+/// with a warning, and entries are created once the tasks they depend on exist, so dependencies
+/// can refer to tasks later in the file.
+pub fn import(path : &path::Path, format : args::ImportFormat, vault_folder : &path::Path, state : &mut state::State, journal : &mut journal::Journal) -> Result<(), error::Error> {
+    let contents = fs::read_to_string(path)?;
+
+    let values = match format {
+        args::ImportFormat::Json => {
+            serde_json::from_str::<Vec<serde_json::Value>>(&contents)
+                .map_err(|err| error::Error::Generic(format!("Expected an array of tasks in {} - {}", format::file(&path.display().to_string()), err)))?
+        },
+    };
+
+    let total = values.len();
+    let mut skipped = 0;
+
+    let mut pending = Vec::new();
+    for (index, value) in values.into_iter().enumerate() {
+        let position = index + 1;
+        match validate(value) {
+            Ok((entry, priority, due)) => {
+                pending.push(Pending { position, entry, priority, due });
+            },
+            Err(err) => {
+                println!("{} Skipped entry {}: {}", format::warning("Warning:"), position, err);
+                skipped += 1;
+            }
+        }
+    }
+
+    // Map from names in the import to the IDs they were created with.
+    let mut created = HashMap::<String, Id>::new();
+
+    loop {
+        let mut progressed = false;
+        let mut remaining = Vec::new();
+
+        for task in pending {
+            match resolve(&task.entry.dependencies, &created, state) {
+                Some(dependencies) => {
+                    let Pending { entry, priority, due, .. } = task;
+                    if let Some(due) = &due {
+                        tasks::warn_if_distant(due);
+                    }
+                    journal.record(state.data.next_id)?;
+                    let new_task = tasks::NewTask {
+                        name : entry.name.clone(),
+                        info : entry.info,
+                        tags : entry.tags,
+                        dependencies,
+                        priority,
+                        due,
+                        ..Default::default()
+                    };
+                    let id = tasks::Task::new(new_task, vault_folder, state)?;
+                    created.insert(entry.name, id);
+                    progressed = true;
+                },
+                None => {
+                    remaining.push(task);
+                },
+            }
+        }
+
+        pending = remaining;
+
+        if pending.is_empty() || !progressed {
+            break;
+        }
+    }
+
+    for task in &pending {
+        println!("{} Skipped entry {}: dependencies could not be found or are circular", format::warning("Warning:"), task.position);
+        skipped += 1;
+    }
+
+    println!("Imported {} of {} task{}", created.len(), total, if total == 1 {""} else {"s"});
+
+    if skipped != 0 {
+        println!("Skipped {} malformed entr{}", skipped, if skipped == 1 {"y"} else {"ies"});
+    }
+
+    Ok(())
+}
+
+/// Parses an entry, checking everything that can be checked before any tasks are created.
+fn validate(value : serde_json::Value) -> Result<(Entry, Option<tasks::Priority>, Option<chrono::NaiveDateTime>), error::Error> {
+    let entry = serde_json::from_value::<Entry>(value)
+        .map_err(|err| error::Error::Generic(format!("Invalid task - {}", err)))?;
+
+    if entry.name.chars().all(|c| c.is_numeric()) {
+        return Err(error::Error::Generic(String::from("Name must not be purely numeric")));
+    }
+
+    let priority = entry.priority
+        .as_deref()
+        .map(|p| <tasks::Priority as clap::ValueEnum>::from_str(p, true))
+        .transpose()
+        .map_err(|_| error::Error::Generic(format!("Invalid priority {}", entry.priority.as_deref().unwrap_or_default())))?;
+
+    let due = entry.due
+        .as_deref()
+        .map(args::parse_datetime)
+        .transpose()
+        .map_err(error::Error::Generic)?;
+
+    Ok((entry, priority, due))
+}
+
+/// Gets the IDs of an entry's dependencies, or None if any of them don't exist yet.
+fn resolve(dependencies : &[Dependency], created : &HashMap<String, Id>, state : &state::State) -> Option<Vec<Id>> {
+    dependencies
+        .iter()
+        .map(|dependency| {
+            match dependency {
+                Dependency::Id(id) => {
+                    state.data.deps.contains_node(*id).then_some(*id)
+                },
+                Dependency::Name(name) => {
+                    created.get(name).copied().or_else(|| {
+                        state.data.index.lookup_exact(name).ok().filter(|id| state.data.deps.contains_node(*id))
+                    })
+                },
+            }
+        })
+        .collect()
+}
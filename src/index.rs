@@ -33,6 +33,11 @@ impl Index {
         }
     }
 
+    /// Iterates over every name and ID pair in the index.
+    pub fn entries(&self) -> impl Iterator<Item = (&String, Id)> {
+        self.map.iter().flat_map(|(name, ids)| ids.iter().map(move |id| (name, *id)))
+    }
+
     pub fn insert(&mut self, name : String, id : Id) {
         match self.map.get_mut(&name) {
             Some(ids) => {
@@ -56,7 +61,24 @@ impl Index {
         }
     }
 
+    /// Gets the IDs of every task with exactly the given name.
+    pub fn ids(&self, name : &str) -> &[Id] {
+        self.map.get(name).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Looks up a task by ID or name, falling back to fuzzy matching of names when no task has
+    /// exactly the given name, in which case the task found is printed.
     pub fn lookup(&self, name_or_id : &String) -> Result<Id, error::Error> {
+        if name_or_id.parse::<Id>().is_ok() || self.map.contains_key(name_or_id) {
+            self.lookup_exact(name_or_id)
+        }
+        else {
+            self.lookup_fuzzy(name_or_id)
+        }
+    }
+
+    /// Looks up a task by ID or exact name.
+    pub fn lookup_exact(&self, name_or_id : &String) -> Result<Id, error::Error> {
         match name_or_id.parse::<Id>() {
             Ok(id) => Ok(id),
             Err(_) => {
@@ -86,8 +108,66 @@ impl Index {
                             Err(error::Error::Generic(format!("Multiple tasks (Ids: [{}]) by that name exist", display_ids)))
                         }
                     },
-                    None => Err(error::Error::Generic(format!("A note by the name {} does not exist", format::task(name)))),
+                    None => Err(error::Error::Generic(format!("A task by the name {} does not exist", format::task(name)))),
+                }
+            }
+        }
+    }
+
+    /// Finds the task whose name best matches, ignoring case, preferring names which start with
+    /// the given text, then contain it, then contain its characters in order.
+    fn lookup_fuzzy(&self, name : &str) -> Result<Id, error::Error> {
+        /// Number of candidates listed when several tasks match equally well.
+        const MAX_CANDIDATES : usize = 5;
+
+        let query = name.to_lowercase();
+
+        let score = |candidate : &str| {
+            let candidate = candidate.to_lowercase();
+            if candidate == query {
+                Some(0)
+            }
+            else if candidate.starts_with(&query) {
+                Some(1)
+            }
+            else if candidate.contains(&query) {
+                Some(2)
+            }
+            else {
+                // Whether the characters of the query appear in order in the candidate.
+                let mut chars = candidate.chars();
+                query.chars().all(|q| chars.any(|c| c == q)).then_some(3)
+            }
+        };
+
+        let matches : Vec<_> = self.entries()
+            .filter_map(|(candidate, id)| score(candidate).map(|s| (s, candidate, id)))
+            .collect();
+
+        let best = match matches.iter().map(|(s, _, _)| *s).min() {
+            Some(best) => best,
+            None => return Err(error::Error::Generic(format!("A task by the name {} does not exist", format::task(name)))),
+        };
+
+        let best : Vec<_> = matches.into_iter().filter(|(s, _, _)| *s == best).collect();
+
+        match best[..] {
+            [(_, candidate, id)] => {
+                println!("No task is named {}, using {} (ID: {})", format::task(name), format::task(candidate), format::id(id));
+                Ok(id)
+            },
+            _ => {
+                let mut candidates : Vec<_> = best
+                    .iter()
+                    .take(MAX_CANDIDATES)
+                    .map(|(_, candidate, id)| format!("{} (ID: {})", format::task(candidate), format::id(*id)))
+                    .collect();
+
+                if best.len() > MAX_CANDIDATES {
+                    candidates.push(format!("and {} more", best.len() - MAX_CANDIDATES));
                 }
+
+                Err(error::Error::Generic(format!("No task is named {}, did you mean one of: {}", format::task(name), candidates.join(", "))))
             }
         }
     }
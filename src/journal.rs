@@ -0,0 +1,199 @@
+use crate::error;
+use crate::tasks;
+use crate::state;
+use crate::format;
+use crate::tasks::Id;
+
+use std::fs;
+use std::path;
+use std::io::Write;
+
+/// Number of operations kept in the journal, older entries are discarded.
+const MAX_ENTRIES : usize = 50;
+
+/// An operation which can be undone, stored as a single line of JSON in the journal.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Entry {
+    pub operation : String,
+    pub timestamp : chrono::NaiveDateTime,
+    pub tasks : Vec<Snapshot>,
+}
+
+/// The contents of a task before an operation, which is `None` if the task did not exist.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    pub id : Id,
+    pub previous : Option<tasks::InternalTask>,
+}
+
+/// Collects snapshots of the tasks affected by an operation before they are changed.
+pub struct Journal {
+    vault_folder : path::PathBuf,
+    entry : Entry,
+}
+
+impl Journal {
+    pub fn new(operation : &str, vault_folder : &path::Path) -> Self {
+        Self {
+            vault_folder : vault_folder.to_path_buf(),
+            entry : Entry {
+                operation : String::from(operation),
+                timestamp : chrono::Local::now().naive_local(),
+                tasks : Vec::new(),
+            },
+        }
+    }
+
+    /// Records the current contents of a task, which should be called before it is modified. Only
+    /// the first snapshot of each task is kept.
+    pub fn record(&mut self, id : Id) -> Result<(), error::Error> {
+        if self.entry.tasks.iter().any(|s| s.id == id) {
+            return Ok(());
+        }
+
+        let previous = if tasks::Task::check_exists(id, &self.vault_folder).is_ok() {
+            Some(tasks::Task::load(id, &self.vault_folder, true)?.data)
+        }
+        else {
+            None
+        };
+
+        self.entry.tasks.push(Snapshot { id, previous });
+
+        Ok(())
+    }
+
+    /// Appends the operation to the journal, if any tasks were recorded.
+    pub fn commit(self) -> Result<(), error::Error> {
+        if self.entry.tasks.is_empty() {
+            return Ok(());
+        }
+
+        let mut lines = read_lines(&self.vault_folder)?;
+        lines.push(serde_json::to_string(&self.entry)?);
+
+        let start = lines.len().saturating_sub(MAX_ENTRIES);
+        write_lines(&self.vault_folder, &lines[start..])
+    }
+}
+
+/// Restores the tasks recorded by the most recent operation and removes it from the journal,
+/// rebuilding the index, dependency graph and metadata cache. Returns `None` if there is nothing
+/// to undo. Operations on tasks which have since been archived can't be undone.
+pub fn undo(vault_folder : &path::Path, state : &mut state::State) -> Result<Option<Entry>, error::Error> {
+    let mut lines = read_lines(vault_folder)?;
+
+    let entry = match lines.pop() {
+        Some(line) => serde_json::from_str::<Entry>(&line)?,
+        None => return Ok(None),
+    };
+
+    // Restoring an archived task into the tasks folder would leave it in both.
+    let archive_folder = vault_folder.join("archive");
+    if let Some(snapshot) = entry.tasks.iter().find(|s| archive_folder.join(format!("{}.toml", s.id)).exists()) {
+        return Err(error::Error::Generic(format!("The last operation ({}) can't be undone, as task {} has since been archived", entry.operation, format::id(snapshot.id))));
+    }
+
+    // Restoring in reverse order so that the earliest snapshot of a task wins.
+    for snapshot in entry.tasks.iter().rev() {
+        let path = vault_folder.join("tasks").join(format!("{}.toml", snapshot.id));
+        match &snapshot.previous {
+            Some(previous) => {
+                tasks::write_atomic(&path, &toml::to_string(previous)?)?;
+            },
+            None => {
+                if path.exists() {
+                    fs::remove_file(&path)?;
+                }
+            }
+        }
+    }
+
+    state.reindex()?;
+
+    write_lines(vault_folder, &lines)?;
+
+    Ok(Some(entry))
+}
+
+fn journal_path(vault_folder : &path::Path) -> path::PathBuf {
+    vault_folder.join("journal.jsonl")
+}
+
+fn read_lines(vault_folder : &path::Path) -> Result<Vec<String>, error::Error> {
+    let path = journal_path(vault_folder);
+
+    if path.exists() {
+        Ok(
+            fs::read_to_string(&path)?
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .map(String::from)
+                .collect()
+        )
+    }
+    else {
+        Ok(Vec::new())
+    }
+}
+
+fn write_lines(vault_folder : &path::Path, lines : &[String]) -> Result<(), error::Error> {
+    let mut file = fs::File::create(journal_path(vault_folder))?;
+
+    for line in lines {
+        writeln!(file, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::archive;
+
+    #[test]
+    fn undo_restores_dependencies() {
+        let vault = testing::TempVault::new();
+        let mut state = vault.state();
+        let first = vault.new_task("first", &mut state);
+        let second = vault.new_task("second", &mut state);
+
+        // Adding a dependency as the depend command does.
+        let mut journal = Journal::new("depend", &vault.path);
+        journal.record(first).unwrap();
+        state.data.deps.insert_edge(first, second).unwrap();
+        let mut task = tasks::Task::load(first, &vault.path, false).unwrap();
+        task.data.dependencies.insert(second);
+        task.save().unwrap();
+        journal.commit().unwrap();
+
+        let entry = undo(&vault.path, &mut state).unwrap().unwrap();
+
+        assert_eq!(entry.operation, "depend");
+        assert!(tasks::Task::load(first, &vault.path, true).unwrap().data.dependencies.is_empty());
+        assert!(state.data.deps.get_nested_deps(first).unwrap().is_empty());
+        assert!(undo(&vault.path, &mut state).unwrap().is_none());
+    }
+
+    #[test]
+    fn undo_refuses_archived_tasks() {
+        let vault = testing::TempVault::new();
+        let mut state = vault.state();
+        let id = vault.new_task("task", &mut state);
+
+        let mut journal = Journal::new("complete", &vault.path);
+        journal.record(id).unwrap();
+        let mut task = tasks::Task::load(id, &vault.path, false).unwrap();
+        task.data.completed = Some(chrono::Local::now().naive_local());
+        task.save().unwrap();
+        journal.commit().unwrap();
+
+        archive::archive(None, &vault.path, &mut state).unwrap();
+
+        assert!(undo(&vault.path, &mut state).is_err());
+        assert!(tasks::Task::check_exists(id, &vault.path).is_err());
+        assert_eq!(read_lines(&vault.path).unwrap().len(), 1);
+    }
+}
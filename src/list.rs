@@ -1,5 +1,7 @@
 use crate::args;
+use crate::archive;
 use crate::error;
+use crate::config;
 use crate::state;
 use crate::tasks;
 use crate::format;
@@ -7,7 +9,9 @@ use crate::tasks::Id;
 
 use std::cmp;
 use std::path;
-use std::collections::HashSet;
+use std::time;
+use std::thread;
+use std::collections::{HashSet, HashMap, BTreeMap, BTreeSet};
 use chrono::SubsecRound;
 
 impl args::ListOptions {
@@ -15,13 +19,24 @@ impl args::ListOptions {
     /// of the arguments provided matters, hence the argument names (because optional arguments
     /// from the profile are overwritten by the additional arguments).
     pub fn combine(profile : &Self, additional : &Self) -> Self {
-        /// Joins two vectors together one after the other, creating a new allocation.
-        fn concat<T : Clone>(a : &Vec<T>, b : &Vec<T>) -> Vec<T> {
-            let mut a = a.clone();
-            a.extend(b.iter().cloned());
+        /// Joins two vectors together one after the other, creating a new allocation, and leaving
+        /// out values of the second which are already in the first (so that combining options
+        /// with a profile they were saved to doesn't repeat them).
+        fn concat<T : Clone + PartialEq>(a : &[T], b : &[T]) -> Vec<T> {
+            let mut a = a.to_vec();
+            for value in b {
+                if !a.contains(value) {
+                    a.push(value.clone());
+                }
+            }
             a
         }
 
+        /// Takes two vectors, and uses the second if it is not empty, otherwise the first.
+        fn join_vecs<T : Clone>(a : &[T], b : &[T]) -> Vec<T> {
+            if b.is_empty() { a.to_vec() } else { b.to_vec() }
+        }
+
         /// Takes two options, and prioritises the second if it is provided in the output, using
         /// the first as a fallback, and returning None if both are None.
         fn join_options<T : Clone>(a : &Option<T>, b : &Option<T>) -> Option<T> {
@@ -35,50 +50,248 @@ impl args::ListOptions {
 
         Self {
             column : concat(&profile.column, &additional.column),
-            order_by : join_options(&profile.order_by, &additional.order_by),
-            order : join_options(&profile.order, &profile.order),
+            order_by : join_vecs(&profile.order_by, &additional.order_by),
+            order : join_options(&profile.order, &additional.order),
+            limit : join_options(&profile.limit, &additional.limit),
             tag : concat(&profile.tag, &additional.tag),
             exclude_tag : concat(&profile.exclude_tag, &additional.exclude_tag),
             priority : concat(&profile.priority, &additional.priority),
             due_before : join_options(&profile.due_before, &additional.due_before),
             due_after : join_options(&profile.due_after, &additional.due_after),
+            has_due : join_options(&profile.has_due, &additional.has_due),
+            no_due : join_options(&profile.no_due, &additional.no_due),
+            overdue : join_options(&profile.overdue, &additional.overdue),
+            hide_unscheduled : join_options(&profile.hide_unscheduled, &additional.hide_unscheduled),
             created_before : join_options(&profile.created_before, &additional.created_before),
             created_after : join_options(&profile.created_after, &additional.created_after),
-            include_completed : profile.include_completed || additional.include_completed,
-            no_dependencies : profile.no_dependencies || additional.no_dependencies,
-            no_dependents : profile.no_dependents || additional.no_dependents,
+            completed_within : join_options(&profile.completed_within, &additional.completed_within),
+            completed_before : join_options(&profile.completed_before, &additional.completed_before),
+            completed_after : join_options(&profile.completed_after, &additional.completed_after),
+            include_completed : join_options(&profile.include_completed, &additional.include_completed),
+            archived : join_options(&profile.archived, &additional.archived),
+            no_dependencies : join_options(&profile.no_dependencies, &additional.no_dependencies),
+            no_dependents : join_options(&profile.no_dependents, &additional.no_dependents),
+            children_of : join_options(&profile.children_of, &additional.children_of),
+            group_by : join_options(&profile.group_by, &additional.group_by),
+            count : join_options(&profile.count, &additional.count),
         }
     }
 }
 
-/// Lists all tasks in the specified vault.
-pub fn list(mut options : args::ListOptions, vault_folder : &path::Path, state : &state::State) -> Result<(), error::Error> {
+impl args::ListOptions {
+    /// Describes the options which are set, as the argument used to set each one along with its
+    /// value.
+    pub fn describe(&self) -> Vec<(&'static str, String)> {
+        fn name<T : clap::ValueEnum>(value : &T) -> String {
+            value.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default()
+        }
 
-    let mut table = comfy_table::Table::new();
-    table
-        .load_preset(comfy_table::presets::UTF8_FULL)
-        .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
-        .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+        fn list(values : Vec<String>) -> Option<String> {
+            (!values.is_empty()).then(|| values.join(", "))
+        }
+
+        fn date_or_datetime(value : &args::DateOrDateTime) -> String {
+            match value {
+                args::DateOrDateTime::DateTime(datetime) => datetime.to_string(),
+                args::DateOrDateTime::Date(date) => date.to_string(),
+            }
+        }
+
+        // Destructuring so that new options can't be missed.
+        let Self {
+            column,
+            order_by,
+            order,
+            limit,
+            tag,
+            exclude_tag,
+            priority,
+            due_before,
+            due_after,
+            has_due,
+            no_due,
+            overdue,
+            hide_unscheduled,
+            created_before,
+            created_after,
+            completed_within,
+            completed_before,
+            completed_after,
+            include_completed,
+            archived,
+            no_dependencies,
+            no_dependents,
+            children_of,
+            group_by,
+            count,
+        } = self;
+
+        let described = [
+            ("-c", list(column.iter().map(name).collect())),
+            ("--order-by", list(order_by.iter().map(name).collect())),
+            ("--order", order.as_ref().map(name)),
+            ("--limit", limit.map(|l| l.to_string())),
+            ("--tag", list(tag.clone())),
+            ("--exclude-tag", list(exclude_tag.clone())),
+            ("--priority", list(priority.iter().map(name).collect())),
+            ("--due-before", due_before.map(|d| d.to_string())),
+            ("--due-after", due_after.map(|d| d.to_string())),
+            ("--has-due", has_due.map(|b| b.to_string())),
+            ("--no-due", no_due.map(|b| b.to_string())),
+            ("--overdue", overdue.map(|b| b.to_string())),
+            ("--hide-unscheduled", hide_unscheduled.map(|b| b.to_string())),
+            ("--created-before", created_before.as_ref().map(date_or_datetime)),
+            ("--created-after", created_after.as_ref().map(date_or_datetime)),
+            ("--completed-within", completed_within.map(|d| d.to_string())),
+            ("--completed-before", completed_before.map(|d| d.to_string())),
+            ("--completed-after", completed_after.map(|d| d.to_string())),
+            ("--include-completed", include_completed.map(|b| b.to_string())),
+            ("--archived", archived.map(|b| b.to_string())),
+            ("--no-dependencies", no_dependencies.map(|b| b.to_string())),
+            ("--no-dependents", no_dependents.map(|b| b.to_string())),
+            ("--children-of", children_of.map(|id| id.to_string())),
+            ("--group-by", group_by.as_ref().map(name)),
+            ("--count", count.map(|b| b.to_string())),
+        ];
+
+        described
+            .into_iter()
+            .filter_map(|(argument, value)| value.map(|value| (argument, value)))
+            .collect()
+    }
+
+    /// Prints the options which are set as a table.
+    pub fn show(&self) {
+        let described = self.describe();
+
+        if described.is_empty() {
+            println!("No options are set");
+            return;
+        }
+
+        let mut table = comfy_table::Table::new();
+        table
+            .load_preset(comfy_table::presets::UTF8_FULL)
+            .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
+            .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+        table.set_header(vec!["Option", "Value"]);
+
+        for (argument, value) in described {
+            table.add_row(vec![String::from(argument), value]);
+        }
+
+        println!("{}", table);
+    }
+}
+
+/// Summary of a task for JSON output.
+#[derive(serde::Serialize)]
+struct Summary<'a> {
+    id : Id,
+    name : &'a str,
+    tags : &'a BTreeSet<String>,
+    priority : &'a tasks::Priority,
+    due : Option<chrono::NaiveDateTime>,
+    tracked : tasks::Duration,
+}
+
+impl<'a> From<&'a tasks::Metadata> for Summary<'a> {
+    fn from(task : &'a tasks::Metadata) -> Self {
+        Self {
+            id : task.id,
+            name : &task.name,
+            tags : &task.tags,
+            priority : &task.priority,
+            due : task.due,
+            tracked : task.tracked,
+        }
+    }
+}
+
+/// Collects the IDs of completed tasks.
+pub fn completed_ids(tasks : &[tasks::Task]) -> HashSet<Id> {
+    tasks.iter().filter_map(|t| if t.data.completed.is_some() { Some(t.data.id) } else { None }).collect()
+}
 
+/// Collects the IDs of tasks which have no incomplete dependencies, direct or indirect.
+pub fn ready_ids(completed_ids : &HashSet<Id>, state : &state::State) -> Result<HashSet<Id>, error::Error> {
+    let mut ready = HashSet::new();
 
-    let tasks = tasks::Task::load_all(vault_folder, true)?;
+    for &id in state.data.deps.edges.keys() {
+        if state.data.deps.get_nested_deps(id)?.iter().all(|d| completed_ids.contains(d)) {
+            ready.insert(id);
+        }
+    }
+
+    Ok(ready)
+}
+
+/// Lists tasks repeatedly, clearing the screen and reloading the vault each time so that changes
+/// made elsewhere are shown, until the program is interrupted. Errors are shown in place of the
+/// list rather than stopping, as they may only last until the next refresh.
+pub fn watch(options : args::ListOptions, seconds : u64, tag_order : config::TagOrder, score_weights : config::ScoreWeights, vault_folder : &path::Path) -> Result<(), error::Error> {
+    loop {
+        // Clearing the screen and moving the cursor to the top left.
+        print!("\x1B[2J\x1B[1;1H");
+
+        let result = state::State::load(vault_folder)
+            .and_then(|state| list(options.clone(), tag_order, score_weights, args::OutputFormat::Human, vault_folder, &state));
+
+        if let Err(err) = result {
+            println!("{}", err);
+        }
+
+        println!("{}", format::greyed_out(&format!("Refreshing every {} second{}, press Ctrl-C to stop", seconds, if seconds == 1 {""} else {"s"})));
+
+        thread::sleep(time::Duration::from_secs(seconds));
+    }
+}
+
+/// Lists all tasks in the specified vault. JSON output is a single array of task summaries,
+/// ignoring columns and grouping, and CSV output ignores grouping.
+pub fn list(mut options : args::ListOptions, tag_order : config::TagOrder, score_weights : config::ScoreWeights, output : args::OutputFormat, vault_folder : &path::Path, state : &state::State) -> Result<(), error::Error> {
+
+    let archived = options.archived.unwrap_or(false);
+
+    let tasks = if archived {
+        archive::load_all(vault_folder)?.iter().map(tasks::Metadata::from).collect()
+    }
+    else {
+        // Using the cached metadata where possible, to avoid loading every task.
+        match state.data.cache.tasks(&state.data.deps) {
+            Some(tasks) => tasks,
+            None => tasks::Task::load_all(vault_folder, true)?.iter().map(tasks::Metadata::from).collect(),
+        }
+    };
 
     // Collect the Ids of completed tasks for the sake of checking if a task has no incomplete dependencies.
-    let completed_ids : HashSet<Id> = tasks.iter().filter_map(|t| if t.data.completed.is_some() { Some(t.data.id) } else { None }).collect();
+    let completed_ids : HashSet<Id> = tasks.iter().filter(|t| t.completed.is_some()).map(|t| t.id).collect();
+
+    // Count how many tasks use each tag, for ordering the tags column.
+    let tag_frequencies = match tag_order {
+        config::TagOrder::Frequency => {
+            let mut frequencies = HashMap::new();
+            for tag in tasks.iter().flat_map(|t| t.tags.iter()) {
+                *frequencies.entry(tag.clone()).or_insert(0) += 1;
+            }
+            Some(frequencies)
+        },
+        config::TagOrder::Alphabetical => None,
+    };
 
-    let mut tasks : Box<dyn Iterator<Item = tasks::Task>> = Box::new(tasks.into_iter());
+    let mut tasks : Box<dyn Iterator<Item = tasks::Metadata>> = Box::new(tasks.into_iter());
 
     // Filter the tasks.
-    if let Some(date) = options.created_before {
-        tasks = Box::new(tasks.filter(move |t| t.data.created.date() <= date));
+    if let Some(bound) = options.created_before {
+        tasks = Box::new(tasks.filter(move |t| bound.compare(&t.created) != cmp::Ordering::Greater));
     }
-    if let Some(date) = options.created_after {
-        tasks = Box::new(tasks.filter(move |t| t.data.created.date() >= date));
+    if let Some(bound) = options.created_after {
+        tasks = Box::new(tasks.filter(move |t| bound.compare(&t.created) != cmp::Ordering::Less));
     }
 
     if let Some(date) = options.due_before {
         tasks = Box::new(tasks.filter(move |t| {
-            match tasks::compare_due_dates(&t.data.due.map(|d| d.date()), &Some(date)) {
+            match tasks::compare_due_dates(&t.due.map(|d| d.date()), &Some(date)) {
                 cmp::Ordering::Less | cmp::Ordering::Equal => true,
                 cmp::Ordering::Greater => false,
             }
@@ -86,22 +299,58 @@ pub fn list(mut options : args::ListOptions, vault_folder : &path::Path, state :
     }
     if let Some(date) = options.due_after {
         tasks = Box::new(tasks.filter(move |t| {
-            match tasks::compare_due_dates(&t.data.due.map(|d| d.date()), &Some(date)) {
+            match tasks::compare_due_dates(&t.due.map(|d| d.date()), &Some(date)) {
                 cmp::Ordering::Greater | cmp::Ordering::Equal => true,
                 cmp::Ordering::Less => false,
             }
         }));
     }
 
-    if !options.include_completed {
-        tasks = Box::new(tasks.filter(|t| t.data.completed.is_none()));
+    // Note that tasks with no due date are treated as due infinitely far in the future by the
+    // filters above, so these are needed to explicitly include or exclude them.
+    if options.has_due.unwrap_or(false) {
+        tasks = Box::new(tasks.filter(|t| t.due.is_some()));
+    }
+    if options.no_due.unwrap_or(false) {
+        tasks = Box::new(tasks.filter(|t| t.due.is_none()));
+    }
+
+    if options.overdue.unwrap_or(false) {
+        let now = chrono::Local::now().naive_local();
+        tasks = Box::new(tasks.filter(move |t| t.completed.is_none() && t.due.map(|d| d < now).unwrap_or(false)));
+    }
+
+    if options.hide_unscheduled.unwrap_or(false) {
+        let now = chrono::Local::now().naive_local();
+        tasks = Box::new(tasks.filter(move |t| t.scheduled.map(|s| s <= now).unwrap_or(true)));
+    }
+
+    if let Some(date) = options.completed_before {
+        tasks = Box::new(tasks.filter(move |t| t.completed.map(|c| c.date() <= date).unwrap_or(false)));
+    }
+    if let Some(date) = options.completed_after {
+        tasks = Box::new(tasks.filter(move |t| t.completed.map(|c| c.date() >= date).unwrap_or(false)));
+    }
+
+    if let Some(within) = options.completed_within {
+        let within = chrono::Duration::from(within);
+        tasks = Box::new(tasks.filter(move |t| {
+            match t.completed {
+                Some(completed) => chrono::Local::now().naive_local() - completed <= within,
+                None => false,
+            }
+        }));
+    }
+    // Archived tasks are all complete, and the completion date filters only leave complete tasks.
+    else if !options.include_completed.unwrap_or(false) && !archived && options.completed_before.is_none() && options.completed_after.is_none() {
+        tasks = Box::new(tasks.filter(|t| t.completed.is_none()));
     }
 
     if !options.tag.is_empty() {
         let specified_tags : HashSet<_> = options.tag.iter().collect();
 
         tasks = Box::new(tasks.filter(move |t| {
-            let task_tags : HashSet<_> = t.data.tags.iter().collect();
+            let task_tags : HashSet<_> = t.tags.iter().collect();
 
             // Non empty intersection of tags means the task should be displayed
             specified_tags.intersection(&task_tags).next().is_some()
@@ -112,7 +361,7 @@ pub fn list(mut options : args::ListOptions, vault_folder : &path::Path, state :
         let specified_tags : HashSet<_> = options.exclude_tag.iter().collect();
 
         tasks = Box::new(tasks.filter(move |t| {
-            let task_tags : HashSet<_> = t.data.tags.iter().collect();
+            let task_tags : HashSet<_> = t.tags.iter().collect();
 
             // If the task contains a tag which was supposed to be excluded, it should be filtered
             // out
@@ -124,97 +373,59 @@ pub fn list(mut options : args::ListOptions, vault_folder : &path::Path, state :
         let specified_priority_levels : HashSet<_> = options.priority.iter().collect();
 
         tasks = Box::new(tasks.filter(move |t| {
-            specified_priority_levels.contains(&t.data.priority)
+            specified_priority_levels.contains(&t.priority)
         }));
     }
 
     // Checks that a task has no incomplete dependencies.
-    if options.no_dependencies {
-        tasks = Box::new(tasks.filter(move |t| {
-            // Get all dependencies (including indirect ones).
-            let all_dependencies = state.data.deps.get_nested_deps(t.data.id);
-            // Check that all of those dependencies are completed.
-            all_dependencies.iter().all(|d| completed_ids.contains(&d))
-        }));
+    if options.no_dependencies.unwrap_or(false) {
+        let ready_ids = ready_ids(&completed_ids, state)?;
+        tasks = Box::new(tasks.filter(move |t| ready_ids.contains(&t.id)));
     }
 
-    if options.no_dependents {
+    if options.no_dependents.unwrap_or(false) {
         let tasks_with_dependents = state.data.deps.get_tasks_with_dependents();
 
         tasks = Box::new(tasks.filter(move |t| {
-            !tasks_with_dependents.contains(&t.data.id)
+            !tasks_with_dependents.contains(&t.id)
         }));
     }
 
+    if let Some(parent) = options.children_of {
+        tasks = Box::new(tasks.filter(move |t| t.parent == Some(parent)));
+    }
+
     let mut tasks : Vec<_> = tasks.collect();
 
-    // Sort the tasks.
-    use super::{OrderBy, Order};
-    match options.order_by.unwrap_or_default() {
-        OrderBy::Id => {
-            match options.order.unwrap_or_default() {
-                Order::Asc => {
-                    tasks.sort_by(|t1, t2| t1.data.id.cmp(&t2.data.id));
-                },
-                Order::Desc => {
-                    tasks.sort_by(|t1, t2| t2.data.id.cmp(&t1.data.id));
-                },
-            }
-        },
-        OrderBy::Name => {
-            match options.order.unwrap_or_default() {
-                Order::Asc => {
-                    tasks.sort_by(|t1, t2| t1.data.name.cmp(&t2.data.name));
-                },
-                Order::Desc => {
-                    tasks.sort_by(|t1, t2| t2.data.name.cmp(&t1.data.name));
-                },
-            }
-        },
-        OrderBy::Due => {
-            match options.order.unwrap_or_default() {
-                Order::Asc => {
-                    tasks.sort_by(|t1, t2| tasks::compare_due_dates(&t1.data.due, &t2.data.due));
-                },
-                Order::Desc => {
-                    tasks.sort_by(|t1, t2| tasks::compare_due_dates(&t2.data.due, &t1.data.due));
-                },
-            }
-        },
-        OrderBy::Priority => {
-            match options.order.unwrap_or_default() {
-                Order::Asc => {
-                    tasks.sort_by(|t1, t2| t1.data.priority.cmp(&t2.data.priority));
-                },
-                Order::Desc => {
-                    tasks.sort_by(|t1, t2| t2.data.priority.cmp(&t1.data.priority));
-                },
-            }
-        },
-        OrderBy::Created => {
-            match options.order.unwrap_or_default() {
-                Order::Asc => {
-                    tasks.sort_by(|t1, t2| t1.data.created.cmp(&t2.data.created));
-                },
-                Order::Desc => {
-                    tasks.sort_by(|t1, t2| t2.data.created.cmp(&t1.data.created));
-                },
-            }
-        },
-        OrderBy::Tracked => {
-            match options.order.unwrap_or_default() {
-                Order::Asc => {
-                    tasks.sort_by(|t1, t2| tasks::TimeEntry::total(&t1.data.time_entries).cmp(&tasks::TimeEntry::total(&t2.data.time_entries)));
-                },
-                Order::Desc => {
-                    tasks.sort_by(|t1, t2| tasks::TimeEntry::total(&t2.data.time_entries).cmp(&tasks::TimeEntry::total(&t1.data.time_entries)));
-                },
-            }
+    // Sort the tasks, comparing each field in turn and then by ID so the order is deterministic.
+    let order_by = if options.order_by.is_empty() { vec![args::OrderBy::default()] } else { options.order_by.clone() };
+    let now = chrono::Local::now().naive_local();
+    tasks.sort_by(|t1, t2| {
+        let ordering = order_by
+            .iter()
+            .fold(cmp::Ordering::Equal, |ordering, order_by| ordering.then_with(|| compare(t1, t2, *order_by, &score_weights, now)));
+
+        match options.order.unwrap_or_default() {
+            args::Order::Asc => ordering,
+            args::Order::Desc => ordering.reverse(),
         }
+        .then_with(|| t1.id.cmp(&t2.id))
+    });
+
+    if let Some(limit) = options.limit {
+        tasks.truncate(limit);
     }
 
-    // Include the required columns
-    let mut headers = vec!["Id", "Name"];
+    if options.count.unwrap_or(false) {
+        println!("{}", tasks.len());
+        return Ok(());
+    }
+
+    if output == args::OutputFormat::Json {
+        let summaries : Vec<_> = tasks.iter().map(Summary::from).collect();
+        println!("{}", serde_json::to_string_pretty(&summaries)?);
+        return Ok(());
+    }
 
     // Remove duplicate columns.
     options.column = {
@@ -233,61 +444,187 @@ pub fn list(mut options : args::ListOptions, vault_folder : &path::Path, state :
             })
             .collect()
     };
-    
-    use super::Column;
-    for column in &options.column {
-        match column {
-            Column::Tracked => {
-                headers.push("Tracked");
-            },
-            Column::Due => {
-                headers.push("Due");
-            },
-            Column::Tags => {
-                headers.push("Tags");
-            },
-            Column::Priority => {
-                headers.push("Priority");
-            },
-            Column::Status => {
-                headers.push("Status");
-            },
-            Column::Created => {
-                headers.push("Created");
-            },
+
+    if output == args::OutputFormat::Csv {
+        print!("{}", csv(&tasks, &options.column, tag_frequencies.as_ref()));
+        return Ok(());
+    }
+
+    match options.group_by {
+        Some(group_by) => {
+            for (label, group) in groups(&tasks, group_by) {
+                println!("{} ({})", label, group.len());
+                println!("{}", table(&group, &options.column, tag_frequencies.as_ref()));
+            }
+        },
+        None => {
+            let tasks : Vec<_> = tasks.iter().collect();
+            println!("{}", table(&tasks, &options.column, tag_frequencies.as_ref()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Compares two tasks by a single field, in ascending order.
+fn compare(t1 : &tasks::Metadata, t2 : &tasks::Metadata, order_by : args::OrderBy, score_weights : &config::ScoreWeights, now : chrono::NaiveDateTime) -> cmp::Ordering {
+    use args::OrderBy;
+
+    match order_by {
+        OrderBy::Id => t1.id.cmp(&t2.id),
+        OrderBy::Name => t1.name.cmp(&t2.name),
+        OrderBy::Due => tasks::compare_due_dates(&t1.due, &t2.due),
+        OrderBy::Priority => t1.priority.cmp(&t2.priority),
+        OrderBy::Created => t1.created.cmp(&t2.created),
+        OrderBy::Modified => t1.modified.cmp(&t2.modified),
+        OrderBy::Tracked => t1.tracked.cmp(&t2.tracked),
+        // Higher scores are more urgent, so come first.
+        OrderBy::Score => score(t2, score_weights, now).total_cmp(&score(t1, score_weights, now)),
+    }
+}
+
+/// Scores how urgent a task is, from its priority, how soon it is due (with tasks which are due
+/// now or overdue scoring the full due weight) and how long ago it was created.
+fn score(task : &tasks::Metadata, weights : &config::ScoreWeights, now : chrono::NaiveDateTime) -> f64 {
+    use tasks::Priority::*;
+
+    let level = match task.priority {
+        Backlog => 0.0,
+        Low => 1.0,
+        Medium => 2.0,
+        High => 3.0,
+    };
+
+    let days = |duration : chrono::Duration| duration.num_seconds() as f64 / 86400.0;
+
+    let proximity = match task.due {
+        Some(due) => 1.0 / (1.0 + f64::max(days(due - now), 0.0) / 7.0),
+        None => 0.0,
+    };
+
+    let age = f64::max(days(now - task.created), 0.0) / 30.0;
+
+    f64::from(weights.priority) * level + f64::from(weights.due) * proximity + f64::from(weights.age) * age
+}
+
+/// Gets the header text for a column.
+fn header(column : &args::Column) -> &'static str {
+    use args::Column;
+
+    match column {
+        Column::Tracked => "Tracked",
+        Column::Due => "Due",
+        Column::Tags => "Tags",
+        Column::Priority => "Priority",
+        Column::Status => "Status",
+        Column::Created => "Created",
+        Column::Modified => "Modified",
+        Column::CompletedAge => "Completed",
+        Column::Scheduled => "Scheduled",
+        Column::Estimate => "Estimate",
+        Column::Checklist => "Checklist",
+    }
+}
+
+/// Formats how much of a task's checklist is done, which is empty if it has no checklist.
+fn checklist(task : &tasks::Metadata) -> String {
+    match task.checklist {
+        (_, 0) => String::new(),
+        (done, total) => format!("{}/{}", done, total),
+    }
+}
+
+/// Renders tasks as RFC 4180 CSV with the specified columns, without any colour.
+fn csv(tasks : &[tasks::Metadata], columns : &[args::Column], tag_frequencies : Option<&HashMap<String, usize>>) -> String {
+
+    use args::Column;
+
+    let mut headers = vec!["Id", "Name"];
+    headers.extend(columns.iter().map(header));
+
+    let mut lines = vec![headers.join(",")];
+
+    for task in tasks {
+        let mut row = vec![task.id.to_string(), format::csv_field(&task.name)];
+
+        for column in columns {
+            let value = match column {
+                Column::Tracked => {
+                    let duration = task.tracked;
+                    if duration == tasks::Duration::zero() { String::new() } else { duration.to_string() }
+                },
+                Column::Due => task.due.map(|d| d.round_subsecs(0).to_string()).unwrap_or_default(),
+                Column::Tags => format::tags(&task.tags, tag_frequencies),
+                Column::Priority => String::from(format::priority_name(&task.priority)),
+                Column::Status => String::from(if task.completed.is_some() { "complete" } else { "incomplete" }),
+                Column::Created => task.created.round_subsecs(0).to_string(),
+                Column::Modified => task.modified.round_subsecs(0).to_string(),
+                Column::CompletedAge => {
+                    task.completed
+                        .map(|completed| format!("{} ago", format::fuzzy_period(chrono::Local::now().naive_local() - completed)))
+                        .unwrap_or_default()
+                },
+                Column::Scheduled => task.scheduled.map(|s| s.round_subsecs(0).to_string()).unwrap_or_default(),
+                Column::Estimate => task.estimate.map(|e| e.to_string()).unwrap_or_default(),
+                Column::Checklist => checklist(task),
+            };
+
+            row.push(format::csv_field(&value));
         }
+
+        lines.push(row.join(","));
     }
 
+    let mut csv = lines.join("\r\n");
+    csv.push_str("\r\n");
+    csv
+}
+
+/// Renders tasks as a table with the specified columns.
+fn table(tasks : &[&tasks::Metadata], columns : &[args::Column], tag_frequencies : Option<&HashMap<String, usize>>) -> comfy_table::Table {
+
+    use args::Column;
+
+    let mut table = comfy_table::Table::new();
+    table
+        .load_preset(comfy_table::presets::UTF8_FULL)
+        .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
+        .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+
+    // Include the required columns
+    let mut headers = vec!["Id", "Name"];
+    headers.extend(columns.iter().map(header));
+
     table.set_header(headers);
 
     for task in tasks {
 
         use comfy_table::Cell;
-        let mut row = vec![Cell::from(task.data.id), Cell::from(task.data.name)];
+        let mut row = vec![Cell::from(task.id), format::cell::task_name(&task.name, &task.priority)];
 
-        for column in &options.column {
+        for column in columns {
             match column {
                 Column::Tracked => {
-                    let duration = tasks::TimeEntry::total(&task.data.time_entries);
+                    let duration = task.tracked;
                     row.push(
                         Cell::from(if duration == tasks::Duration::zero() { String::new() } else { duration.to_string() })
                     );
                 },
                 Column::Due => {
-                    row.push(match task.data.due {
-                        Some(due) => format::cell::due_date(&due, task.data.completed.is_none()),
+                    row.push(match task.due {
+                        Some(due) => format::cell::due_date(&due, task.completed.is_none()),
                         None => Cell::from(String::new())
                     });
                 },
                 Column::Tags => {
-                    row.push(Cell::new(format::hash_set(&task.data.tags)?));
+                    row.push(format::cell::tags(&task.tags, tag_frequencies));
                 },
                 Column::Priority => {
-                    row.push(format::cell::priority(&task.data.priority));
+                    row.push(format::cell::priority(&task.priority));
                 },
                 Column::Status => {
                     row.push(
-                        Cell::new(if task.data.completed.is_some() {
+                        Cell::new(if task.completed.is_some() {
                             String::from("complete")
                         }
                         else {
@@ -296,7 +633,34 @@ pub fn list(mut options : args::ListOptions, vault_folder : &path::Path, state :
                     );
                 },
                 Column::Created => {
-                    row.push(Cell::new(task.data.created.round_subsecs(0).to_string()));
+                    row.push(Cell::new(task.created.round_subsecs(0).to_string()));
+                },
+                Column::Modified => {
+                    row.push(Cell::new(task.modified.round_subsecs(0).to_string()));
+                },
+                Column::CompletedAge => {
+                    row.push(match task.completed {
+                        Some(completed) => {
+                            let age = chrono::Local::now().naive_local() - completed;
+                            Cell::new(format!("{} ago", format::fuzzy_period(age)))
+                        },
+                        None => Cell::from(String::new())
+                    });
+                },
+                Column::Scheduled => {
+                    row.push(match task.scheduled {
+                        Some(scheduled) => Cell::new(scheduled.round_subsecs(0).to_string()),
+                        None => Cell::from(String::new())
+                    });
+                },
+                Column::Estimate => {
+                    row.push(match task.estimate {
+                        Some(estimate) => Cell::new(estimate.to_string()),
+                        None => Cell::from(String::new())
+                    });
+                },
+                Column::Checklist => {
+                    row.push(Cell::new(checklist(task)));
                 },
             }
         }
@@ -304,7 +668,216 @@ pub fn list(mut options : args::ListOptions, vault_folder : &path::Path, state :
         table.add_row(row);
     }
 
-    println!("{}", table);
+    table
+}
 
-    Ok(())
+/// Partitions tasks into labelled groups, in a stable order, omitting empty groups. When grouping
+/// by tag, a task appears once under each of its tags.
+fn groups(tasks : &[tasks::Metadata], group_by : args::GroupBy) -> Vec<(String, Vec<&tasks::Metadata>)> {
+    use args::GroupBy;
+
+    let groups : Vec<(String, Vec<&tasks::Metadata>)> = match group_by {
+        GroupBy::Tag => {
+            let mut by_tag : BTreeMap<&String, Vec<&tasks::Metadata>> = BTreeMap::new();
+            let mut untagged = Vec::new();
+
+            for task in tasks {
+                if task.tags.is_empty() {
+                    untagged.push(task);
+                }
+                for tag in &task.tags {
+                    by_tag.entry(tag).or_default().push(task);
+                }
+            }
+
+            let mut groups : Vec<_> = by_tag.into_iter().map(|(tag, group)| (format::highlight(tag).to_string(), group)).collect();
+            groups.push((format::greyed_out("untagged").to_string(), untagged));
+            groups
+        },
+        GroupBy::Priority => {
+            use tasks::Priority::*;
+            [High, Medium, Low, Backlog]
+                .into_iter()
+                .map(|priority| {
+                    let group = tasks.iter().filter(|t| t.priority == priority).collect();
+                    (format::priority(&priority), group)
+                })
+                .collect()
+        },
+        GroupBy::Status => {
+            vec![
+                (format::highlight("incomplete").to_string(), tasks.iter().filter(|t| t.completed.is_none()).collect()),
+                (format::greyed_out("complete").to_string(), tasks.iter().filter(|t| t.completed.is_some()).collect()),
+            ]
+        },
+    };
+
+    groups.into_iter().filter(|(_, group)| !group.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets a boolean list option to read or set it.
+    type Accessor = fn(&mut args::ListOptions) -> &mut Option<bool>;
+
+    /// Accessors for each of the boolean list options which can be set by a profile.
+    fn booleans() -> Vec<(&'static str, Accessor)> {
+        vec![
+            ("has_due", |o| &mut o.has_due),
+            ("no_due", |o| &mut o.no_due),
+            ("overdue", |o| &mut o.overdue),
+            ("hide_unscheduled", |o| &mut o.hide_unscheduled),
+            ("include_completed", |o| &mut o.include_completed),
+            ("archived", |o| &mut o.archived),
+            ("no_dependencies", |o| &mut o.no_dependencies),
+            ("no_dependents", |o| &mut o.no_dependents),
+            ("count", |o| &mut o.count),
+        ]
+    }
+
+    #[test]
+    fn combine_booleans_prefer_additional() {
+        let values = [None, Some(false), Some(true)];
+
+        for (name, field) in booleans() {
+            for profile_value in values {
+                for additional_value in values {
+                    let mut profile = args::ListOptions::default();
+                    *field(&mut profile) = profile_value;
+                    let mut additional = args::ListOptions::default();
+                    *field(&mut additional) = additional_value;
+
+                    let mut combined = args::ListOptions::combine(&profile, &additional);
+
+                    assert_eq!(
+                        *field(&mut combined),
+                        additional_value.or(profile_value),
+                        "{} with profile {:?} and additional {:?}", name, profile_value, additional_value
+                    );
+                }
+            }
+        }
+    }
+
+    fn date(day : u32) -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd_opt(2023, 1, day).unwrap()
+    }
+
+    #[test]
+    fn combine_order_prefers_additional() {
+        let profile = args::ListOptions { order : Some(args::Order::Asc), ..Default::default() };
+        let additional = args::ListOptions { order : Some(args::Order::Desc), ..Default::default() };
+
+        assert_eq!(args::ListOptions::combine(&profile, &additional).order, Some(args::Order::Desc));
+        assert_eq!(args::ListOptions::combine(&profile, &Default::default()).order, Some(args::Order::Asc));
+    }
+
+    #[test]
+    fn combine_order_by_is_replaced_by_additional() {
+        let profile = args::ListOptions { order_by : vec![args::OrderBy::Due, args::OrderBy::Name], ..Default::default() };
+        let additional = args::ListOptions { order_by : vec![args::OrderBy::Priority], ..Default::default() };
+
+        assert_eq!(args::ListOptions::combine(&profile, &additional).order_by, vec![args::OrderBy::Priority]);
+        assert_eq!(args::ListOptions::combine(&profile, &Default::default()).order_by, vec![args::OrderBy::Due, args::OrderBy::Name]);
+    }
+
+    #[test]
+    fn combine_concatenates_lists() {
+        let profile = args::ListOptions {
+            column : vec![args::Column::Due],
+            tag : vec![String::from("work")],
+            exclude_tag : vec![String::from("later")],
+            priority : vec![tasks::Priority::High],
+            ..Default::default()
+        };
+        let additional = args::ListOptions {
+            column : vec![args::Column::Tags],
+            tag : vec![String::from("home")],
+            exclude_tag : vec![String::from("someday")],
+            priority : vec![tasks::Priority::Backlog],
+            ..Default::default()
+        };
+
+        let combined = args::ListOptions::combine(&profile, &additional);
+
+        assert_eq!(combined.column, vec![args::Column::Due, args::Column::Tags]);
+        assert_eq!(combined.tag, vec![String::from("work"), String::from("home")]);
+        assert_eq!(combined.exclude_tag, vec![String::from("later"), String::from("someday")]);
+        assert_eq!(combined.priority, vec![tasks::Priority::High, tasks::Priority::Backlog]);
+    }
+
+    #[test]
+    fn combine_optional_values_prefer_additional() {
+        let profile = args::ListOptions {
+            limit : Some(5),
+            due_before : Some(date(1)),
+            due_after : Some(date(2)),
+            created_before : Some(args::DateOrDateTime::Date(date(3))),
+            created_after : Some(args::DateOrDateTime::Date(date(4))),
+            completed_within : Some(tasks::Duration::from_minutes(60)),
+            completed_before : Some(date(5)),
+            completed_after : Some(date(6)),
+            children_of : Some(1),
+            group_by : Some(args::GroupBy::Tag),
+            ..Default::default()
+        };
+        let additional = args::ListOptions {
+            limit : Some(10),
+            due_before : Some(date(11)),
+            due_after : Some(date(12)),
+            created_before : Some(args::DateOrDateTime::Date(date(13))),
+            created_after : Some(args::DateOrDateTime::DateTime(date(14).and_hms_opt(9, 0, 0).unwrap())),
+            completed_within : Some(tasks::Duration::from_minutes(120)),
+            completed_before : Some(date(15)),
+            completed_after : Some(date(16)),
+            children_of : Some(2),
+            group_by : Some(args::GroupBy::Status),
+            ..Default::default()
+        };
+
+        assert_eq!(args::ListOptions::combine(&profile, &additional), additional);
+        assert_eq!(args::ListOptions::combine(&profile, &Default::default()), profile);
+        assert_eq!(args::ListOptions::combine(&Default::default(), &additional), additional);
+    }
+
+    #[test]
+    fn combine_repeatedly_does_not_duplicate() {
+        let profile = args::ListOptions {
+            column : vec![args::Column::Due, args::Column::Tags],
+            tag : vec![String::from("work")],
+            ..Default::default()
+        };
+        let additional = args::ListOptions {
+            column : vec![args::Column::Tags, args::Column::Tracked],
+            exclude_tag : vec![String::from("later")],
+            ..Default::default()
+        };
+
+        // As happens when saving the combined options as the default and listing again.
+        let once = args::ListOptions::combine(&profile, &additional);
+        let twice = args::ListOptions::combine(&once, &additional);
+
+        assert_eq!(once.column, vec![args::Column::Due, args::Column::Tags, args::Column::Tracked]);
+        assert_eq!(twice, once);
+    }
+
+    #[test]
+    fn boolean_options_parse_as_tri_state() {
+        use clap::Parser;
+
+        let parse = |extra : &[&str]| {
+            let args = args::Args::try_parse_from([&["toru", "list"], extra].concat()).unwrap();
+            match args.command {
+                args::Command::List { options, .. } => options.include_completed,
+                _ => unreachable!(),
+            }
+        };
+
+        assert_eq!(parse(&[]), None);
+        assert_eq!(parse(&["--include-completed"]), Some(true));
+        assert_eq!(parse(&["--include-completed=true"]), Some(true));
+        assert_eq!(parse(&["--include-completed=false"]), Some(false));
+    }
 }
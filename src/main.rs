@@ -11,6 +11,19 @@ mod graph;
 mod stats;
 mod config;
 mod format;
+mod dedup;
+mod next;
+mod timesheet;
+mod timer;
+mod search;
+mod prompt;
+mod journal;
+mod tag;
+mod import;
+mod archive;
+mod backup;
+#[cfg(test)]
+mod testing;
 
 use args::*;
 
@@ -23,15 +36,26 @@ fn main() {
         },
         Err(err) => {
             println!("{}", err);
-            std::process::exit(1);
+            std::process::exit(err.exit_code());
         }
     }
 }
 
 fn program() -> Result<(), error::Error> {
-    let command = Args::accept_command();
+    let Args { command, config : config_path, format : output, no_color, vault_override } = Args::accept_command();
 
-    let mut config = config::Config::load()?;
+    // Colour is disabled by the NO_COLOR environment variable if it is set to anything.
+    let no_color_env = std::env::var_os("NO_COLOR").map(|v| !v.is_empty()).unwrap_or(false);
+    format::set_colour(!no_color && !no_color_env);
+
+    let mut config = config::Config::load(config_path.as_deref())?;
+
+    format::set_tag_colours(config.tag_colours.clone());
+    format::set_due_thresholds(config.due_very_close_hours, config.due_close_days);
+
+    if let Some(name) = vault_override {
+        config.override_vault(&name)?;
+    }
 
     if let Command::Vault(command) = command {
         match command {
@@ -40,7 +64,7 @@ fn program() -> Result<(), error::Error> {
                 println!("Created vault {}", format::vault(&name));
             },
             VaultCommand::Disconnect { name } => {
-                vault::disconnect(&name, &mut config)?;
+                let name = vault::disconnect(&name, &mut config)?;
                 println!("Disconnected vault {}", format::vault(&name));
             },
             VaultCommand::Connect { name , path } => {
@@ -48,16 +72,52 @@ fn program() -> Result<(), error::Error> {
                 println!("Connected vault {}", format::vault(&name));
             },
             VaultCommand::Delete { name } => {
-                vault::delete(&name, &mut config)?;
+                let name = vault::delete(&name, &mut config)?;
                 println!("Deleted vault {}", format::vault(&name));
             },
             VaultCommand::List => {
                 config.list_vaults()?;
             },
+            VaultCommand::Tidy => {
+                let vault_folder = &config.current_vault()?.1;
+                vault::tidy(vault_folder)?;
+            },
+            VaultCommand::Check { fix } => {
+                let vault_folder = &config.current_vault()?.1;
+                let mut state = state::State::load(vault_folder)?;
+                let result = vault::check(fix, vault_folder, &mut state);
+                state.save()?;
+                result?;
+            },
+            VaultCommand::Info => {
+                let (name, vault_folder) = config.current_vault()?;
+                vault::info(name, vault_folder)?;
+            },
+            VaultCommand::Current { path_only, name_only } => {
+                let (name, path) = config.current_vault()?;
+                if path_only {
+                    println!("{}", path.display());
+                }
+                else if name_only {
+                    println!("{}", name);
+                }
+                else {
+                    println!("{} {}", format::vault(name), path.display());
+                }
+            },
             VaultCommand::Rename { old_name, new_name } => {
-                config.rename_vault(&old_name, new_name.clone())?;
+                let old_name = config.rename_vault(&old_name, new_name.clone())?;
                 println!("Renamed vault {} to {}", format::vault(&old_name), format::vault(&new_name));
-            }
+            },
+            VaultCommand::Backup { output } => {
+                let (name, vault_folder) = config.current_vault()?;
+                let output = backup::backup(name, output, vault_folder)?;
+                println!("Backed up vault {} to {}", format::vault(name), format::file(&output.display().to_string()));
+            },
+            VaultCommand::Restore { archive, name, path } => {
+                let path = backup::restore(&archive, name.clone(), path, &mut config)?;
+                println!("Restored vault {} to {}", format::vault(&name), format::file(&path.display().to_string()));
+            },
         }
     }
     else if let Command::Config(command) = command {
@@ -73,25 +133,220 @@ fn program() -> Result<(), error::Error> {
                     }
                 }
             },
+            ConfigCommand::Pager { pager } => {
+                match pager {
+                    Some(pager) => {
+                        config.pager = pager;
+                        println!("Updated pager command to: {}", config.pager);
+                    },
+                    None => {
+                        println!("Current pager command: {}", config.pager);
+                    }
+                }
+            },
+            ConfigCommand::TagColour { tag, colour, unset } => {
+                if unset {
+                    match config.tag_colours.remove(&tag) {
+                        Some(_) => println!("Removed colour of tag {}", tag),
+                        None => println!("Tag {} has no colour set", tag),
+                    }
+                }
+                else {
+                    match colour {
+                        Some(colour) => {
+                            config.tag_colours.insert(tag.clone(), colour);
+                            format::set_tag_colours(config.tag_colours.clone());
+                            println!("Updated colour of tag {} to {}", format::tag(&tag), format::hex(colour));
+                        },
+                        None => {
+                            match config.tag_colours.get(&tag) {
+                                Some(colour) => println!("Current colour of tag {}: {}", format::tag(&tag), format::hex(*colour)),
+                                None => println!("Tag {} has no colour set", tag),
+                            }
+                        }
+                    }
+                }
+            },
+            ConfigCommand::InfoEditor { editor, unset } => {
+                if unset {
+                    config.info_editor = None;
+                    println!("Info editor command now uses the editor command: {}", config.editor);
+                }
+                else {
+                    match editor {
+                        Some(editor) => {
+                            println!("Updated info editor command to: {}", editor);
+                            config.info_editor = Some(editor);
+                        },
+                        None => {
+                            println!("Current info editor command: {}", config.info_editor());
+                        }
+                    }
+                }
+            },
+            ConfigCommand::DeleteMode { mode } => {
+                match mode {
+                    Some(mode) => {
+                        config.delete_mode = mode;
+                        println!("Updated delete mode to: {}", config.delete_mode);
+                    },
+                    None => {
+                        println!("Current delete mode: {}", config.delete_mode);
+                    }
+                }
+            },
+            ConfigCommand::TagOrder { order } => {
+                match order {
+                    Some(order) => {
+                        config.tag_order = order;
+                        println!("Updated tag order to: {}", config.tag_order);
+                    },
+                    None => {
+                        println!("Current tag order: {}", config.tag_order);
+                    }
+                }
+            },
+            ConfigCommand::WeekStart { day } => {
+                match day {
+                    Some(day) => {
+                        config.week_start = day;
+                        println!("Updated week start to: {}", config.week_start);
+                    },
+                    None => {
+                        println!("Current week start: {}", config.week_start);
+                    }
+                }
+            },
+            ConfigCommand::DueThresholds { very_close_hours, close_days } => {
+                if very_close_hours.is_none() && close_days.is_none() {
+                    println!("Current due thresholds: very close within {} hours, close within {} days", config.due_very_close_hours, config.due_close_days);
+                }
+                else {
+                    let very_close_hours = very_close_hours.unwrap_or(config.due_very_close_hours);
+                    let close_days = close_days.unwrap_or(config.due_close_days);
+
+                    if u64::from(very_close_hours) > u64::from(close_days) * 24 {
+                        return Err(error::Error::Generic(format!("The very close threshold ({} hours) must not be longer than the close threshold ({} days)", very_close_hours, close_days)));
+                    }
+
+                    config.due_very_close_hours = very_close_hours;
+                    config.due_close_days = close_days;
+                    println!("Updated due thresholds to: very close within {} hours, close within {} days", config.due_very_close_hours, config.due_close_days);
+                }
+            },
+            ConfigCommand::ScoreWeights { priority, due, age } => {
+                if priority.is_none() && due.is_none() && age.is_none() {
+                    println!("Current score weights: {}", config.score_weights);
+                }
+                else {
+                    let weights = &mut config.score_weights;
+                    weights.priority = priority.unwrap_or(weights.priority);
+                    weights.due = due.unwrap_or(weights.due);
+                    weights.age = age.unwrap_or(weights.age);
+                    println!("Updated score weights to: {}", config.score_weights);
+                }
+            },
             ConfigCommand::Profile(command) => {
                 match command {
-                    ProfileCommand::New { name, options } => {
-                        config.create_profile(name.clone(), options)?;
+                    ProfileCommand::New { name, vault, options } => {
+                        if vault {
+                            let vault_folder = &config.current_vault()?.1;
+                            let mut vault_config = config::VaultConfig::load(vault_folder)?;
+                            vault_config.create_profile(name.clone(), *options)?;
+                            vault_config.save(vault_folder)?;
+                        }
+                        else {
+                            config.create_profile(name.clone(), *options)?;
+                        }
                         println!("Created profile {}", format::profile(&name))
                     },
-                    ProfileCommand::Delete { name } => {
-                        config.delete_profile(&name)?;
+                    ProfileCommand::Delete { name, vault } => {
+                        if vault {
+                            let vault_folder = &config.current_vault()?.1;
+                            let mut vault_config = config::VaultConfig::load(vault_folder)?;
+                            vault_config.delete_profile(&name)?;
+                            vault_config.save(vault_folder)?;
+                        }
+                        else {
+                            config.delete_profile(&name)?;
+                        }
                         println!("Deleted profile {}", format::profile(&name))
                     },
                     ProfileCommand::List => {
-                        config.list_profiles()?;
+                        let vault_config = match config.current_vault() {
+                            Ok((_, vault_folder)) => Some(config::VaultConfig::load(vault_folder)?),
+                            Err(_) => None,
+                        };
+                        config.list_profiles(vault_config.as_ref())?;
+                    },
+                    ProfileCommand::Show { name } => {
+                        let options = match config.current_vault() {
+                            Ok((_, vault_folder)) => {
+                                let vault_config = config::VaultConfig::load(vault_folder)?;
+                                config.resolve_profile(&name, &vault_config)?.clone()
+                            },
+                            Err(_) => config.get_profile(&name)?.clone(),
+                        };
+                        println!("Profile {}:", format::profile(&name));
+                        options.show();
+                    }
+                }
+            },
+            ConfigCommand::DefaultList { options, clear } => {
+                if clear {
+                    if options != ListOptions::default() {
+                        return Err(error::Error::Generic(String::from("List options cannot be given when clearing the default")));
+                    }
+                    if config.clear_default_profile() {
+                        println!("Cleared default list options");
+                    }
+                    else {
+                        println!("No default list options are set");
+                    }
+                }
+                else if options == ListOptions::default() {
+                    match config.default_profile() {
+                        Some(default) => {
+                            println!("Current default list options:");
+                            default.show();
+                        },
+                        None => {
+                            println!("No default list options are set");
+                        }
+                    }
+                }
+                else {
+                    config.set_default_profile(options);
+                    println!("Updated default list options");
+                }
+            },
+            ConfigCommand::Show => {
+                let vault_config = match config.current_vault() {
+                    Ok((_, vault_folder)) => Some(config::VaultConfig::load(vault_folder)?),
+                    Err(_) => None,
+                };
+                config.show(vault_config.as_ref());
+            },
+            ConfigCommand::Export { path, include_vaults } => {
+                let exported = config.export(include_vaults)?;
+                match path {
+                    Some(path) => {
+                        std::fs::write(&path, exported)?;
+                        println!("Exported configuration to {}", format::file(&path.display().to_string()));
+                    },
+                    None => {
+                        print!("{}", exported);
                     }
                 }
-            }
+            },
+            ConfigCommand::Import { path, overwrite } => {
+                config.import(&path, overwrite)?;
+                println!("Imported configuration from {}", format::file(&path.display().to_string()));
+            },
         }
     }
     else if let Command::Switch { name } = command {
-        config.switch(&name)?;
+        let name = config.switch(&name)?;
         println!("Switched to vault {}", format::vault(&name));
     }
     else if let Command::Git { args } = command {
@@ -114,91 +369,510 @@ fn program() -> Result<(), error::Error> {
     }
     // Commands that require loading in the state.
     else {
-        let vault_folder = &config.current_vault()?.1;
+        let vault_folder = &config.current_vault()?.1.clone();
         let mut state = state::State::load(vault_folder)?;
+        // Result of commands which operate on several tasks, reported after saving the state so
+        // that the tasks which succeeded are kept.
+        let mut batch_result = Ok(());
+        // Snapshots of the tasks changed by the command, so that it can be undone.
+        let mut journal = None;
 
         match command {
-            Command::New { name, info, tag, dependency, priority, due } => {
-                let id = tasks::Task::new(name.clone(), info, tag, dependency, priority, due, vault_folder, &mut state)?;
+            Command::New { name, info, edit, tag, dependency, blocks, parent, priority, due, scheduled, estimate, repeat, yes } => {
+                if let Some(due) = &due {
+                    tasks::confirm_due(due, yes)?;
+                }
+                let blocks = blocks
+                    .iter()
+                    .map(|id_or_name| state.data.index.lookup(id_or_name))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let parent = parent
+                    .map(|id_or_name| state.data.index.lookup(&id_or_name))
+                    .transpose()?;
+                let info = if edit {
+                    edit::new_info(vault_folder, config.info_editor())?
+                }
+                else if info.as_deref() == Some("-") {
+                    let mut contents = String::new();
+                    std::io::Read::read_to_string(&mut std::io::stdin(), &mut contents)?;
+                    edit::info_from(contents)
+                }
+                else {
+                    info
+                };
+                let journal = journal.insert(journal::Journal::new("new", vault_folder));
+                journal.record(state.data.next_id)?;
+                for dependent in &blocks {
+                    journal.record(*dependent)?;
+                }
+                let new_task = tasks::NewTask {
+                    name : name.clone(),
+                    info,
+                    tags : tag,
+                    dependencies : dependency,
+                    blocks,
+                    parent,
+                    priority,
+                    due,
+                    scheduled,
+                    estimate,
+                    repeat,
+                };
+                let id = tasks::Task::new(new_task, vault_folder, &mut state)?;
                 println!("Created task {} (ID: {})", format::task(&name), format::id(id));
             },
-            Command::Delete { id_or_name } => {
-                let id = state.data.index.lookup(&id_or_name)?;
-                let task = tasks::Task::load(id, vault_folder, false)?;
-                let name = task.data.name.clone();
-                state.data.index.remove(task.data.name.clone(), task.data.id);
-                // Removing the task from others which list it as a dependency.
-                if let (true, dependents) = state.data.deps.remove_node(task.data.id) {
-                    for dependent in dependents {
-                        let mut task = tasks::Task::load(dependent, vault_folder, false)?;
-                        task.data.dependencies.remove(&id);
-                        task.save()?;
+            Command::Delete { id_or_name, yes, recursive, reparent } => {
+                let journal = journal.insert(journal::Journal::new("delete", vault_folder));
+                batch_result = batch(&id_or_name, |id_or_name| {
+                    let confirm = !yes && prompt::is_interactive();
+                    // Names are only matched fuzzily when the task is shown before deleting it.
+                    let id = if confirm { state.data.index.lookup(id_or_name)? } else { state.data.index.lookup_exact(id_or_name)? };
+                    if confirm {
+                        let task = tasks::Task::load(id, vault_folder, true)?;
+                        println!("Task {} (ID: {})", format::task(&task.data.name), format::id(id));
+                        if !prompt::confirm("Delete this task?")? {
+                            println!("Skipped deleting task {}", format::id(id));
+                            return Ok(());
+                        }
+                    }
+                    let subtasks = tasks::Task::subtasks(id, &state);
+                    if !subtasks.is_empty() && !recursive && !reparent {
+                        return Err(error::Error::Generic(format!("Task {} has subtasks, use {} to delete them as well or {} to move them to its parent", format::id(id), format::command("--recursive"), format::command("--reparent"))));
                     }
-                }
-                task.delete()?;
 
-                println!("Deleted task {} (ID: {})", format::task(&name), format::id(id));
+                    if reparent {
+                        let parent = state.data.cache.get(id).and_then(|t| t.parent);
+                        for child in state.data.cache.children(id) {
+                            journal.record(child)?;
+                            let mut task = tasks::Task::load(child, vault_folder, false)?;
+                            task.data.parent = parent;
+                            state.data.cache.insert(&task);
+                            task.save()?;
+                        }
+                    }
+
+                    // Deleting the deepest subtasks first, so no task is left with a missing parent.
+                    let to_delete = if recursive { subtasks } else { Vec::new() };
+                    for id in to_delete.into_iter().rev().chain([id]) {
+                        journal.record(id)?;
+                        for dependent in state.data.deps.dependents(id) {
+                            journal.record(dependent)?;
+                        }
+                        let name = tasks::Task::remove(id, config.delete_mode, vault_folder, &mut state)?;
+
+                        println!("Deleted task {} (ID: {})", format::task(&name), format::id(id));
+                    }
+                    Ok(())
+                });
+            },
+            Command::Import { path, format } => {
+                let journal = journal.insert(journal::Journal::new("import", vault_folder));
+                import::import(&path, format, vault_folder, &mut state, journal)?;
             },
-            Command::View { id_or_name } => {
+            Command::Archive { before } => {
+                let count = archive::archive(before, vault_folder, &mut state)?;
+                println!("Archived {} completed task{}", count, if count == 1 {""} else {"s"});
+            },
+            Command::Move { id_or_name, vault } => {
+                let id = state.data.index.lookup(&id_or_name)?;
+                let (destination_name, destination_folder) = config.get_vault(&vault)?.clone();
+                if destination_folder == *vault_folder {
+                    return Err(error::Error::Generic(format!("Task {} is already in vault {}", format::id(id), format::vault(&destination_name))));
+                }
+                let destination_state = state::State::load(&destination_folder)?;
+                let new_id = tasks::Task::move_vault(id, config.delete_mode, vault_folder, &mut state, &destination_folder, destination_state)?;
+                println!("Moved task {} to vault {} (ID: {})", format::id(id), format::vault(&destination_name), format::id(new_id));
+            },
+            Command::Duplicate { id_or_name, name, with_deps } => {
+                let id = state.data.index.lookup(&id_or_name)?;
+                let next_id = state.data.next_id;
+                let new_id = tasks::Task::duplicate(id, name, with_deps, vault_folder, &mut state)?;
+                let task = tasks::Task::load(new_id, vault_folder, true)?;
+                println!("Created task {} (ID: {}) as a copy of {}", format::task(&task.data.name), format::id(new_id), format::id(id));
+                if with_deps {
+                    println!("Copied {} dependencies", new_id - next_id);
+                }
+            },
+            Command::Dedup { match_tags, dry_run } => {
+                let journal = journal.insert(journal::Journal::new("dedup", vault_folder));
+                dedup::dedup(match_tags, dry_run, config.delete_mode, vault_folder, &mut state, journal)?;
+            },
+            Command::View { id_or_name, json, detailed } => {
                 let id = state.data.index.lookup(&id_or_name)?;
                 let task = tasks::Task::load(id, vault_folder, true)?;
-                task.display(vault_folder, &state)?;
+                let output = if json { OutputFormat::Json } else { output };
+                task.display(output, vault_folder, &state, detailed)?;
+            },
+            Command::Open { id_or_name } => {
+                let id = state.data.index.lookup(&id_or_name)?;
+                edit::open_info(id, vault_folder, &config.pager)?;
             },
-            Command::Edit { id_or_name, info } => {
+            Command::Edit { id_or_name, info, name, priority, due, clear_due, add_tag, remove_tag, yes } => {
                 let id = state.data.index.lookup(&id_or_name)?;
+                journal.insert(journal::Journal::new("edit", vault_folder)).record(id)?;
+                let fields = edit::Fields {
+                    name,
+                    priority,
+                    due : if clear_due { Some(None) } else { due.map(Some) },
+                    add_tags : add_tag,
+                    remove_tags : remove_tag,
+                };
                 if info {
-                    edit::edit_info(id, vault_folder.clone(), &config.editor)?;
+                    edit::edit_info(id, vault_folder.clone(), config.info_editor(), &mut state)?;
+                }
+                else if !fields.is_empty() {
+                    edit::edit_fields(id, fields, yes, vault_folder, &mut state)?;
                 }
                 else {
-                    edit::edit_raw(id, vault_folder.clone(), &config.editor, &mut state)?;
+                    edit::edit_raw(id, yes, vault_folder.clone(), &config.editor, &mut state)?;
                 }
                 println!("Updated task {}", format::id(id));
             },
-            Command::Track { id_or_name, duration, date, message } => {
+            Command::Track { timer, targets, duration, from_file, date, message, force } => {
+                match (timer, targets.split_last(), from_file) {
+                    (Some(TimerCommand::Start { id_or_name }), _, _) => {
+                        timer::start(&id_or_name, vault_folder, &mut state)?;
+                    },
+                    (Some(TimerCommand::Stop { message }), _, _) => {
+                        if let Some(timer) = &state.data.timer {
+                            journal.insert(journal::Journal::new("track", vault_folder)).record(timer.id)?;
+                        }
+                        // Nothing to undo if no time entry was added.
+                        if !timer::stop(message, vault_folder, &mut state)? {
+                            journal = None;
+                        }
+                    },
+                    (Some(TimerCommand::Status), _, _) => {
+                        timer::status(vault_folder, &state)?;
+                    },
+                    (None, _, Some(path)) => {
+                        let journal = journal.insert(journal::Journal::new("track", vault_folder));
+                        // Rows which were imported are kept even if others failed.
+                        batch_result = timesheet::import(&path, force, vault_folder, &mut state, journal);
+                    },
+                    (None, Some((last, rest)), None) => {
+                        let (duration, id_or_name) = match duration {
+                            Some(duration) => {
+                                // A duration after the tasks as well would be ambiguous.
+                                if last.parse::<tasks::Duration>().is_ok() {
+                                    return Err(error::Error::Generic(format!("The duration was given both with --duration and as {}, please only give it once", last)));
+                                }
+                                (duration, &targets[..])
+                            },
+                            None => {
+                                if rest.is_empty() {
+                                    return Err(error::Error::Generic(String::from("Expected the tasks to track time against followed by a duration [format: HH:MM], or the duration given with --duration")));
+                                }
+                                let duration = last.parse::<tasks::Duration>().map_err(|err| error::Error::Generic(format!("Invalid duration {} - {}", last, err)))?;
+                                (duration, rest)
+                            },
+                        };
+                        let journal = journal.insert(journal::Journal::new("track", vault_folder));
+                        batch_result = batch(id_or_name, |id_or_name| {
+                            let id = state.data.index.lookup_exact(id_or_name)?;
+                            journal.record(id)?;
+                            let entry = tasks::TimeEntry::new(duration, date, message.clone());
+                            tasks::Task::track(id, entry, force, vault_folder, &mut state)?;
+                            Ok(())
+                        });
+                    },
+                    // Enforced by clap.
+                    (None, None, None) => unreachable!(),
+                }
+            },
+            Command::Untrack { id_or_name, index } => {
                 let id = state.data.index.lookup(&id_or_name)?;
+                journal.insert(journal::Journal::new("untrack", vault_folder)).record(id)?;
                 let mut task = tasks::Task::load(id, vault_folder, false)?;
-                let entry =  tasks::TimeEntry::new(duration, date, message);
-                task.data.time_entries.push(entry);
+                let entry = task.untrack(index)?;
+                state.data.cache.insert(&task);
                 task.save()?;
+                println!("Removed time entry {} [{}] {} from task {}", entry.duration, entry.logged_date, entry.message.unwrap_or_default(), format::id(id));
+            },
+            Command::Check(command) => {
+                let journal = journal.insert(journal::Journal::new("check", vault_folder));
+                match command {
+                    CheckCommand::Add { id_or_name, text } => {
+                        let id = state.data.index.lookup(&id_or_name)?;
+                        journal.record(id)?;
+                        let mut task = tasks::Task::load(id, vault_folder, false)?;
+                        let index = task.add_checklist_item(&text)?;
+                        state.data.cache.insert(&task);
+                        task.save()?;
+                        println!("Added checklist item {} to task {}", index, format::id(id));
+                    },
+                    CheckCommand::Toggle { id_or_name, index } => {
+                        let id = state.data.index.lookup(&id_or_name)?;
+                        journal.record(id)?;
+                        let mut task = tasks::Task::load(id, vault_folder, false)?;
+                        let done = task.toggle_checklist_item(index)?.done;
+                        state.data.cache.insert(&task);
+                        task.save()?;
+                        println!("Marked checklist item {} of task {} as {}", index, format::id(id), if done {"done"} else {"not done"});
+                    },
+                }
             },
             Command::Stats(command) => {
                 use StatsCommand::*;
+                let last_stats = state.data.last_stats;
+                let week_start = config.week_start.into();
+                let period = |days, since_last| {
+                    match last_stats {
+                        Some(last) if since_last => stats::Period::Since(last),
+                        _ => stats::Period::Days(days),
+                    }
+                };
+                let calendar_period = |days, since_last, weeks, months| {
+                    match (weeks, months) {
+                        (Some(weeks), _) => stats::Period::weeks(weeks, week_start),
+                        (None, Some(months)) => stats::Period::months(months),
+                        (None, None) => period(days, since_last),
+                    }
+                };
                 match command {
-                    Tracked { days } => {
-                        stats::time_per_tag(days, vault_folder)?;
+                    Tracked { days, since_last, weeks, months, count_only, tag, by_day } => {
+                        let period = calendar_period(days, since_last, weeks, months);
+                        if by_day {
+                            stats::time_per_day(period, tag.as_deref(), output, vault_folder)?;
+                        }
+                        else {
+                            stats::time_per_tag(period, tag.as_deref(), count_only, output, vault_folder)?;
+                        }
+                    },
+                    Entries { days, since_last, tag } => {
+                        stats::time_entries(period(days, since_last), &tag, output, vault_folder)?;
+                    },
+                    Estimates => {
+                        stats::estimates(output, vault_folder)?;
+                    },
+                    Due { days } => {
+                        stats::due(days, output, vault_folder)?;
+                    },
+                    Burndown { days } => {
+                        stats::burndown(days, output, vault_folder)?;
+                    },
+                    Velocity { weeks } => {
+                        stats::velocity(weeks, output, vault_folder)?;
+                    },
+                    Completed { days, since_last, weeks, months, from, to, csv, count_only } => {
+                        let period = if from.is_some() || to.is_some() {
+                            stats::Period::Between(from, to)
+                        }
+                        else {
+                            calendar_period(days, since_last, weeks, months)
+                        };
+                        let output = if csv { args::OutputFormat::Csv } else { output };
+                        stats::completed_tasks(period, count_only, output, vault_folder)?;
+                    }
+                }
+                state.data.last_stats = Some(chrono::Local::now().naive_local());
+            },
+            Command::Tag(command) => {
+                match command {
+                    TagCommand::Rename { old, new } => {
+                        let journal = journal.insert(journal::Journal::new("tag rename", vault_folder));
+                        let updated = tag::rename(&old, &new, vault_folder, &mut state, journal)?;
+                        println!("Renamed tag {} to {} on {} task{}", old, new, updated, if updated == 1 {""} else {"s"});
+                    },
+                    TagCommand::List => {
+                        tag::list(config.tag_order, output, vault_folder)?;
+                    }
+                }
+            },
+            Command::Search { query, info, regex } => {
+                search::search(&query, info, regex, vault_folder)?;
+            },
+            Command::Next { include_blocked, tag } => {
+                next::next(include_blocked, &tag, output, vault_folder, &state)?;
+            },
+            Command::Complete { id_or_name, cascade, note, force } => {
+                let journal = journal.insert(journal::Journal::new("complete", vault_folder));
+                batch_result = batch(&id_or_name, |id_or_name| {
+                    let id = state.data.index.lookup_exact(id_or_name)?;
+                    let completed = chrono::Local::now().naive_local();
+                    journal.record(id)?;
+                    let mut task = tasks::Task::load(id, vault_folder, false)?;
+                    let subtasks = if cascade { tasks::Task::subtasks(id, &state) } else { Vec::new() };
+                    if task.data.completed.is_none() && !force {
+                        // Subtasks which will be completed by cascading don't hold the task up.
+                        let blocking : Vec<_> = task.incomplete_dependencies(vault_folder, &state)?
+                            .into_iter()
+                            .filter(|(dependency, _)| !subtasks.contains(dependency))
+                            .map(|(dependency, name)| format!("{} (ID: {})", format::task(&name), format::id(dependency)))
+                            .collect();
+                        if !blocking.is_empty() {
+                            return Err(error::Error::Generic(format!("Task {} has incomplete dependencies: {} (use --force to complete it anyway)", format::id(id), blocking.join(", "))));
+                        }
+                    }
+                    // Completing an already complete task shouldn't create another occurrence.
+                    let next = match task.data.completed {
+                        Some(_) => None,
+                        None => {
+                            if task.data.repeat.is_some() {
+                                journal.record(state.data.next_id)?;
+                            }
+                            task.recur(completed, vault_folder, &mut state)?
+                        },
+                    };
+                    task.data.completed = Some(completed);
+                    if let Some(note) = &note {
+                        task.append_note(note, completed);
+                    }
+                    state.data.cache.insert(&task);
+                    task.save()?;
+                    println!("Marked task {} as complete", format::id(id));
+                    if let Some(next) = next {
+                        println!("Created next occurrence as task {}", format::id(next));
+                    }
+
+                    if cascade {
+                        for subtask in &subtasks {
+                            journal.record(*subtask)?;
+                        }
+                        let count = tasks::Task::complete_all(&subtasks, completed, vault_folder, &mut state)?;
+                        println!("Marked {} subtask{} as complete", count, if count == 1 {""} else {"s"});
+                    }
+
+                    Ok(())
+                });
+            },
+            Command::Depend { id_or_name, on } => {
+                let id = state.data.index.lookup(&id_or_name)?;
+                if !state.data.deps.contains_node(on) {
+                    return Err(error::Error::Generic(format!("No task with an ID of {} exists", format::id(on))));
+                }
+                if !state.data.deps.insert_edge(id, on)? {
+                    return Err(error::Error::Generic(format!("Task {} already depends on {}", format::id(id), format::id(on))));
+                }
+                // Returning before the state is saved discards the new edge.
+                if let Some(cycle) = state.data.deps.find_cycle() {
+                    return Err(error::Error::Generic(format!("Dependency not added due to circular dependency: {}", graph::format_cycle(&cycle))));
+                }
+                journal.insert(journal::Journal::new("depend", vault_folder)).record(id)?;
+                let mut task = tasks::Task::load(id, vault_folder, false)?;
+                task.data.dependencies.insert(on);
+                state.data.cache.insert(&task);
+                task.save()?;
+                println!("Task {} now depends on {}", format::id(id), format::id(on));
+            },
+            Command::Undepend { id_or_name, from } => {
+                let id = state.data.index.lookup(&id_or_name)?;
+                if !state.data.deps.remove_edge(id, from) {
+                    return Err(error::Error::Generic(format!("Task {} does not depend on {}", format::id(id), format::id(from))));
+                }
+                journal.insert(journal::Journal::new("undepend", vault_folder)).record(id)?;
+                let mut task = tasks::Task::load(id, vault_folder, false)?;
+                task.data.dependencies.remove(&from);
+                state.data.cache.insert(&task);
+                task.save()?;
+                println!("Task {} no longer depends on {}", format::id(id), format::id(from));
+            },
+            Command::Graph { output : path } => {
+                let tasks = tasks::Task::load_all_as_map(vault_folder, true)?;
+                let dot = state.data.deps.to_dot(&tasks);
+                match path {
+                    Some(path) => {
+                        std::fs::write(&path, dot)?;
+                        println!("Dependency graph written to {}", format::file(&path.display().to_string()));
                     },
-                    Completed { days } => {
-                        stats::completed_tasks(days, vault_folder)?;
+                    None => {
+                        print!("{}", dot);
                     }
                 }
             },
-            Command::Complete { id_or_name } => {
+            Command::Reopen { id_or_name } => {
                 let id = state.data.index.lookup(&id_or_name)?;
+                journal.insert(journal::Journal::new("reopen", vault_folder)).record(id)?;
                 let mut task = tasks::Task::load(id, vault_folder, false)?;
-                task.data.completed = Some(chrono::Local::now().naive_local());
+                if task.data.completed.is_none() {
+                    return Err(error::Error::Generic(format!("Task {} is not complete", format::id(id))));
+                }
+                task.data.completed = None;
+                state.data.cache.insert(&task);
                 task.save()?;
-                println!("Marked task {} as complete", format::id(id));
+                println!("Reopened task {}", format::id(id));
+            },
+            Command::Reindex => {
+                let count = state.reindex()?;
+                println!("Reindexed {} task{}", count, if count == 1 {""} else {"s"});
+            },
+            Command::Undo => {
+                match journal::undo(vault_folder, &mut state)? {
+                    Some(entry) => {
+                        println!("Undid {} from {} ({} task{} restored)", entry.operation, entry.timestamp.format("%Y-%m-%d %H:%M"), entry.tasks.len(), if entry.tasks.len() == 1 {""} else {"s"});
+                    },
+                    None => {
+                        return Err(error::Error::Generic(String::from("There are no operations to undo")));
+                    }
+                }
             },
-            Command::List { profile : profile_name, options : additional } => {
+            Command::List { profile : profile_name, options : additional, save_default, watch } => {
                 let options = match profile_name {
                     Some(profile_name) => {
-                        let profile = config.get_profile(&profile_name)?;
+                        let vault_config = config::VaultConfig::load(vault_folder)?;
+                        let profile = config.resolve_profile(&profile_name, &vault_config)?;
                         ListOptions::combine(profile, &additional)
                     },
                     None => {
-                        additional
+                        match config.default_profile() {
+                            Some(default) => ListOptions::combine(default, &additional),
+                            None => additional,
+                        }
                     }
                 };
-                list::list(options, vault_folder, &state)?;
+
+                if save_default {
+                    config.set_default_profile(options.clone());
+                }
+
+                match watch {
+                    Some(seconds) => {
+                        if output != OutputFormat::Human {
+                            return Err(error::Error::Generic(String::from("Watching the list is only supported with human readable output")));
+                        }
+                        list::watch(options, seconds, config.tag_order, config.score_weights, vault_folder)?;
+                    },
+                    None => {
+                        list::list(options, config.tag_order, config.score_weights, output, vault_folder, &state)?;
+                    }
+                }
             },
             // All commands which are dealt with in if let chain at start.
             Command::Vault(_) | Command::Config(_) | Command::Git { args : _ } | Command::Svn { args : _ } | Command::Switch { name : _ } | Command::GitIgnore | Command::SvnIgnore => unreachable!(),
         }
 
         state.save()?;
+        if let Some(journal) = journal {
+            journal.commit()?;
+        }
+        batch_result?;
     }
 
-    config.save()?;
+    config.save(config_path.as_deref())?;
 
     Ok(())
 }
+
+/// Runs an operation for each of the provided tasks, reporting failures without stopping the
+/// remaining operations. A single failing task gives back its error unchanged.
+fn batch<F : FnMut(&String) -> Result<(), error::Error>>(ids_or_names : &[String], mut operation : F) -> Result<(), error::Error> {
+    if let [id_or_name] = ids_or_names {
+        return operation(id_or_name);
+    }
+
+    let mut failed = Vec::new();
+
+    for id_or_name in ids_or_names {
+        if let Err(err) = operation(id_or_name) {
+            println!("{}: {}", id_or_name, err);
+            failed.push(id_or_name.clone());
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    }
+    else {
+        Err(error::Error::Generic(format!("{} of {} tasks failed: {}", failed.len(), ids_or_names.len(), failed.join(", "))))
+    }
+}
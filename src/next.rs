@@ -0,0 +1,46 @@
+use crate::args;
+use crate::list;
+use crate::tasks;
+use crate::error;
+use crate::state;
+use crate::format;
+
+use std::path;
+
+/// Displays the most important incomplete task, by priority, then due date, then creation date.
+/// Tasks with incomplete dependencies are only considered if `include_blocked` is set, and if any
+/// tags are given only tasks with one of them are considered.
+pub fn next(include_blocked : bool, tags : &[String], output : args::OutputFormat, vault_folder : &path::Path, state : &state::State) -> Result<(), error::Error> {
+
+    let tasks = tasks::Task::load_all(vault_folder, true)?;
+    let completed_ids = list::completed_ids(&tasks);
+    let ready_ids = list::ready_ids(&completed_ids, state)?;
+
+    let next = tasks
+        .into_iter()
+        .filter(|t| t.data.completed.is_none())
+        .filter(|t| include_blocked || ready_ids.contains(&t.data.id))
+        .filter(|t| tags.is_empty() || tags.iter().any(|tag| t.data.tags.contains(tag)))
+        .min_by(|t1, t2| {
+            t2.data.priority.cmp(&t1.data.priority)
+                .then_with(|| tasks::compare_due_dates(&t1.data.due, &t2.data.due))
+                .then_with(|| t1.data.created.cmp(&t2.data.created))
+        });
+
+    match next {
+        Some(task) => {
+            task.display(output, vault_folder, state, false)?;
+
+            if output == args::OutputFormat::Human && !ready_ids.contains(&task.data.id) {
+                println!();
+                println!("{} This task is blocked by incomplete dependencies", format::warning("Note:"));
+            }
+
+            Ok(())
+        },
+        None => {
+            let scope = if tags.is_empty() { String::new() } else { format!(" tagged {}", tags.join(" or ")) };
+            Err(error::Error::Generic(format!("No {}tasks{} to work on", if include_blocked { "" } else { "actionable " }, scope)))
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use crate::error;
+
+use std::io;
+use std::io::{Write, IsTerminal};
+
+/// Asks the user a yes or no question on stdin, defaulting to no.
+pub fn confirm(prompt : &str) -> Result<bool, error::Error> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+
+    let mut response = String::new();
+    io::stdin().read_line(&mut response)?;
+
+    Ok(matches!(response.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Whether input and output are both a terminal, and so a user is likely present to answer
+/// prompts.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
@@ -0,0 +1,70 @@
+use crate::tasks;
+use crate::error;
+use crate::format;
+
+use std::path;
+
+/// Lists tasks whose name, or info if `info` is set, matches the query case-insensitively. The
+/// query is matched as a substring unless `regex` is set.
+pub fn search(query : &str, info : bool, regex : bool, vault_folder : &path::Path) -> Result<(), error::Error> {
+
+    let pattern = if regex { query.to_string() } else { regex::escape(query) };
+    let matcher = regex::RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|err| error::Error::Generic(format!("Invalid regular expression - {}", err)))?;
+
+    let mut tasks = tasks::Task::load_all(vault_folder, true)?;
+    tasks.sort_by_key(|t| t.data.id);
+
+    let mut table = comfy_table::Table::new();
+    table
+        .load_preset(comfy_table::presets::UTF8_FULL)
+        .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
+        .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+
+    if info {
+        table.set_header(vec!["Id", "Name", "Info"]);
+    }
+    else {
+        table.set_header(vec!["Id", "Name"]);
+    }
+
+    for task in &tasks {
+        // First line of the info which matches, shown in place of the full info.
+        let info_line = match (&task.data.info, info) {
+            (Some(task_info), true) => task_info.lines().find(|l| matcher.is_match(l)),
+            _ => None,
+        };
+
+        if !matcher.is_match(&task.data.name) && info_line.is_none() {
+            continue;
+        }
+
+        let mut row = vec![task.data.id.to_string(), highlight(&task.data.name, &matcher)];
+        if info {
+            row.push(info_line.map(|l| highlight(l.trim(), &matcher)).unwrap_or_default());
+        }
+
+        table.add_row(row);
+    }
+
+    println!("{}", table);
+
+    Ok(())
+}
+
+/// Highlights all matches within the text.
+fn highlight(text : &str, matcher : &regex::Regex) -> String {
+    let mut highlighted = String::new();
+    let mut last = 0;
+
+    for found in matcher.find_iter(text) {
+        highlighted.push_str(&text[last..found.start()]);
+        highlighted.push_str(&format::highlight(found.as_str()).to_string());
+        last = found.end();
+    }
+    highlighted.push_str(&text[last..]);
+
+    highlighted
+}
@@ -1,30 +1,65 @@
+use crate::vault;
 use crate::error;
 use crate::tasks;
+use crate::format;
 use crate::index;
 use crate::graph;
 use crate::tasks::Id;
 
 use std::fs;
+use std::mem;
 use std::path;
 use std::io;
 use std::io::{Write, Seek};
+use std::collections::BTreeMap;
+use serde::Deserialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+/// Version of the task metadata cache, to be incremented whenever `tasks::Metadata` changes.
+const CACHE_VERSION : u32 = 4;
 
 
 pub struct State {
     file : fs::File,
+    vault_location : path::PathBuf,
     pub data : InternalState,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct InternalState {
     pub next_id : Id,
+    /// When the stats command was last run, for use with `--since-last`.
+    #[serde(default)]
+    pub last_stats : Option<chrono::NaiveDateTime>,
+    /// The running timer started by `track start`, if any.
+    #[serde(default)]
+    pub timer : Option<Timer>,
     pub index : index::Index,
     pub deps : graph::Graph,
+    /// Cached metadata of each task, which is rebuilt on load if it is missing or outdated.
+    #[serde(default, deserialize_with = "Cache::deserialize_lenient")]
+    pub cache : Cache,
+}
+
+#[serde_as]
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+pub struct Cache {
+    version : u32,
+    #[serde_as(as = "BTreeMap<DisplayFromStr, _>")]
+    tasks : BTreeMap<Id, tasks::Metadata>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Timer {
+    pub id : Id,
+    pub started : chrono::NaiveDateTime,
 }
 
 impl State {
     /// This function should be called after creating or checking that the "tasks" folder exists.
     pub fn load(vault_location : &path::Path) -> Result<Self, error::Error> {
+        vault::migrate_legacy_layout(vault_location)?;
+
         let path = vault_location.join("state.toml");
 
         if path.exists() && path.is_file() {
@@ -36,33 +71,36 @@ impl State {
                 .create(true)
                 .open(&path)?;
 
-            let data = toml::from_str::<InternalState>(&contents)?;
+            let mut data = toml::from_str::<InternalState>(&contents)?;
+
+            if data.cache.version != CACHE_VERSION {
+                data.cache = Cache::create(&tasks::Task::load_all(vault_location, true)?);
+            }
 
             Ok(Self {
                 file,
+                vault_location : vault_location.to_path_buf(),
                 data,
             })
         }
         else {
 
             // Calculating the next ID if necessary.
-            let mut max_id : i128 = -1;
-            for id in vault_location.join("tasks").read_dir()?.filter_map(|p| p.ok()).map(|p| p.path()).filter(|p| p.extension().map(|s| s.to_str()) == Some(Some("toml"))).filter_map(|p| p.file_stem().map(|x| x.to_str().map(|y| y.to_string()))).flatten().filter_map(|p| p.parse::<Id>().ok()) {
-
-                if i128::try_from(id).unwrap() > max_id {
-                    max_id = i128::from(id);
-                }
-            }
+            let next_id = max_id(vault_location)?.map(|id| id + 1).unwrap_or(0);
 
             // Calculating out the index and graph.
             let tasks = tasks::Task::load_all(vault_location, true)?;
             let index = index::Index::create(&tasks);
+            let cache = Cache::create(&tasks);
             let deps = graph::Graph::create(tasks);
 
             let data = InternalState {
-                next_id : u64::try_from(max_id + 1).unwrap(),
+                next_id,
+                last_stats : None,
+                timer : None,
                 index,
                 deps,
+                cache,
             };
 
             let mut file = fs::File::options()
@@ -78,6 +116,7 @@ impl State {
 
             let task = Self {
                 file,
+                vault_location : vault_location.to_path_buf(),
                 data,
             };
 
@@ -85,20 +124,157 @@ impl State {
         }
     }
 
+    /// Rebuilds the index, dependency graph and metadata cache from the task files, returning the
+    /// number of tasks found.
+    pub fn reindex(&mut self) -> Result<usize, error::Error> {
+        let tasks = tasks::Task::load_all(&self.vault_location, true)?;
+        let count = tasks.len();
+
+        self.data.index = index::Index::create(&tasks);
+        self.data.cache = Cache::create(&tasks);
+        self.data.deps = graph::Graph::create(tasks);
+
+        Ok(count)
+    }
+
     pub fn save(self) -> Result<(), error::Error> {
 
         let Self {
-            mut file,
-            data,
-        } = self; 
+            file,
+            vault_location,
+            mut data,
+        } = self;
+
+        // Guards against overwriting an existing task if the next ID has fallen behind, such as
+        // from tasks being created out-of-band.
+        if let Some(max_id) = max_id(&vault_location)? {
+            if data.next_id <= max_id {
+                println!("{} Next ID {} was not greater than existing ID {}, repairing", format::warning("Warning:"), format::id(data.next_id), format::id(max_id));
+                data.next_id = max_id + 1;
+            }
+        }
 
         let file_contents = toml::to_string(&data)?;
 
-        file.set_len(0)?;
-        file.seek(io::SeekFrom::Start(0))?;
-        file.write_all(file_contents.as_bytes())?;
+        // The file is replaced rather than written to, so the handle is closed first.
+        mem::drop(file);
+
+        tasks::write_atomic(&vault_location.join("state.toml"), &file_contents)
+    }
+
+}
+
+/// Gets the largest ID among the task files in the vault, including archived tasks so that their
+/// IDs are never reused, if there are any.
+fn max_id(vault_location : &path::Path) -> Result<Option<Id>, error::Error> {
+    let mut max = None;
+
+    for folder in ["tasks", "archive"] {
+        let folder = vault_location.join(folder);
+        if !folder.exists() {
+            continue;
+        }
+
+        let folder_max = folder
+            .read_dir()?
+            .filter_map(|p| p.ok())
+            .map(|p| p.path())
+            .filter(|p| p.extension().map(|s| s.to_str()) == Some(Some("toml")))
+            .filter_map(|p| p.file_stem().and_then(|x| x.to_str()).and_then(|x| x.parse::<Id>().ok()))
+            .max();
 
-        Ok(())
+        max = max.max(folder_max);
     }
 
+    Ok(max)
+}
+
+impl Cache {
+    pub fn create(tasks : &[tasks::Task]) -> Self {
+        Self {
+            version : CACHE_VERSION,
+            tasks : tasks.iter().map(|t| (t.data.id, tasks::Metadata::from(t))).collect(),
+        }
+    }
+
+    /// Updates the cached metadata of a task, which should be called whenever it is saved (so it
+    /// is cached as modified now).
+    pub fn insert(&mut self, task : &tasks::Task) {
+        let mut metadata = tasks::Metadata::from(task);
+        metadata.modified = chrono::Local::now().naive_local();
+        self.tasks.insert(task.data.id, metadata);
+    }
+
+    pub fn remove(&mut self, id : Id) {
+        self.tasks.remove(&id);
+    }
+
+    pub fn get(&self, id : Id) -> Option<&tasks::Metadata> {
+        self.tasks.get(&id)
+    }
+
+    /// Gets the IDs of the direct subtasks of a task.
+    pub fn children(&self, id : Id) -> Vec<Id> {
+        self.tasks.values().filter(|t| t.parent == Some(id)).map(|t| t.id).collect()
+    }
+
+    /// Gets the metadata of all tasks, or None if the cache has drifted from the dependency graph
+    /// (such as from task files being added or removed out-of-band).
+    pub fn tasks(&self, deps : &graph::Graph) -> Option<Vec<tasks::Metadata>> {
+        if self.version == CACHE_VERSION && self.tasks.keys().eq(deps.edges.keys()) {
+            Some(self.tasks.values().cloned().collect())
+        }
+        else {
+            None
+        }
+    }
+
+    /// Reads the cache, treating one which can't be read (such as one from an older version) as
+    /// missing rather than failing to load the state.
+    fn deserialize_lenient<'de, D : serde::Deserializer<'de>>(deserializer : D) -> Result<Self, D::Error> {
+        let value = toml::Value::deserialize(deserializer)?;
+        Ok(value.try_into::<Self>().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn next_id_persists_across_loads() {
+        let vault = testing::TempVault::new();
+        let mut state = vault.state();
+        assert_eq!(vault.new_task("first", &mut state), 0);
+        assert_eq!(vault.new_task("second", &mut state), 1);
+        state.save().unwrap();
+
+        // Saved through a temporary file, which is renamed over the state file.
+        assert!(!vault.path.join("state.toml.tmp").exists());
+        assert_eq!(vault.state().data.next_id, 2);
+
+        // Without a state file, the next ID comes from the task files.
+        fs::remove_file(vault.path.join("state.toml")).unwrap();
+        assert_eq!(vault.state().data.next_id, 2);
+    }
+
+    #[test]
+    fn legacy_notes_folder_is_migrated() {
+        let vault = testing::TempVault::new();
+        let mut state = vault.state();
+        vault.new_task("first", &mut state);
+        vault.new_task("second", &mut state);
+        mem::drop(state);
+
+        fs::remove_file(vault.path.join("state.toml")).unwrap();
+        fs::rename(vault.path.join("tasks"), vault.path.join("notes")).unwrap();
+
+        let state = vault.state();
+
+        assert!(vault.path.join("tasks").join("1.toml").is_file());
+        assert!(!vault.path.join("notes").exists());
+        assert_eq!(state.data.next_id, 2);
+        assert_eq!(state.data.index.lookup(&String::from("second")).unwrap(), 1);
+    }
 }
@@ -1,13 +1,134 @@
+use crate::args;
 use crate::tasks;
 use crate::error;
+use crate::format;
 
+use std::cmp;
 use std::path;
 use std::collections::BTreeMap;
 use chrono::SubsecRound;
 
-pub fn completed_tasks(days : u16, vault_folder : &path::Path) -> Result<(), error::Error> {
+/// The period of time over which stats are reported.
+pub enum Period {
+    /// The last number of days.
+    Days(u16),
+    /// Since a point in time (such as the last time stats were viewed).
+    Since(chrono::NaiveDateTime),
+    /// Between two dates (both inclusive), either of which may be open.
+    Between(Option<chrono::NaiveDate>, Option<chrono::NaiveDate>),
+}
+
+impl Period {
+    /// The current week and the weeks before it, where weeks begin on the given day.
+    pub fn weeks(weeks : u16, week_start : chrono::Weekday) -> Self {
+        use chrono::Datelike;
+
+        let today = chrono::Local::now().naive_local().date();
+        let into_week = (today.weekday().num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7;
+        let start = today - chrono::Duration::days(i64::from(into_week) + 7 * (i64::from(weeks) - 1));
+
+        Period::Since(start.and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    /// The current calendar month and the months before it.
+    pub fn months(months : u16) -> Self {
+        use chrono::Datelike;
+
+        let today = chrono::Local::now().naive_local().date();
+        // Counting months from year zero, to step back across years.
+        let month = i64::from(today.year()) * 12 + i64::from(today.month0()) - (i64::from(months) - 1);
+        let start = chrono::NaiveDate::from_ymd_opt(month.div_euclid(12) as i32, month.rem_euclid(12) as u32 + 1, 1).unwrap();
+
+        Period::Since(start.and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    fn contains(&self, datetime : chrono::NaiveDateTime) -> bool {
+        match self {
+            Period::Days(days) => chrono::Local::now().naive_local() - datetime < chrono::Duration::days(i64::from(*days)),
+            Period::Since(since) => datetime >= *since,
+            Period::Between(..) => self.contains_date(datetime.date()),
+        }
+    }
+
+    fn contains_date(&self, date : chrono::NaiveDate) -> bool {
+        match self {
+            Period::Days(days) => chrono::Local::now().naive_local().date() - date < chrono::Duration::days(i64::from(*days)),
+            // Time entries only store a date, so entries from the same day are included.
+            Period::Since(since) => date >= since.date(),
+            Period::Between(from, to) => from.is_none_or(|from| date >= from) && to.is_none_or(|to| date <= to),
+        }
+    }
+
+    /// The first date within the period, if it has one.
+    fn start_date(&self) -> Option<chrono::NaiveDate> {
+        match self {
+            Period::Days(days) => Some(chrono::Local::now().naive_local().date() - chrono::Duration::days(i64::from(*days) - 1)),
+            Period::Since(since) => Some(since.date()),
+            Period::Between(from, _) => *from,
+        }
+    }
+}
+
+pub fn completed_tasks(period : Period, count_only : bool, output : args::OutputFormat, vault_folder : &path::Path) -> Result<(), error::Error> {
     let tasks = tasks::Task::load_all(vault_folder, true)?;
-    
+
+    if count_only {
+        let count = tasks
+            .iter()
+            .filter_map(|t| t.data.completed)
+            .filter(|completed_date| period.contains(*completed_date))
+            .count();
+
+        println!("{}", count);
+
+        return Ok(());
+    }
+
+    // Tasks completed in the future (from clock skew or edits) are counted as recent.
+    let completed : Vec<_> = tasks
+        .iter()
+        .filter_map(|t| t.data.completed.map(|c| (t, c)))
+        .filter(|(_, completed_date)| period.contains(*completed_date))
+        .collect();
+
+    if output == args::OutputFormat::Json {
+        #[derive(serde::Serialize)]
+        struct Completed<'a> {
+            id : tasks::Id,
+            name : &'a str,
+            completed : chrono::NaiveDateTime,
+        }
+
+        let completed : Vec<_> = completed
+            .iter()
+            .map(|(task, completed)| Completed { id : task.data.id, name : &task.data.name, completed : *completed })
+            .collect();
+
+        println!("{}", serde_json::to_string_pretty(&completed)?);
+
+        return Ok(());
+    }
+
+    if output == args::OutputFormat::Csv {
+        let mut lines = vec![String::from("Name,Completed,Tags,Tracked")];
+        for (task, completed_date) in &completed {
+            let tracked = tasks::TimeEntry::total(&task.data.time_entries);
+            lines.push([
+                format::csv_field(&task.data.name),
+                completed_date.round_subsecs(0).to_string(),
+                format::csv_field(&format::tags(&task.data.tags, None)),
+                if tracked == tasks::Duration::zero() { String::new() } else { tracked.to_string() },
+            ].join(","));
+        }
+
+        let mut csv = lines.join("\r\n");
+        csv.push_str("\r\n");
+        print!("{}", csv);
+
+        return Ok(());
+    }
+
+
     let mut table = comfy_table::Table::new();
     table
         .load_preset(comfy_table::presets::UTF8_FULL)
@@ -15,16 +136,11 @@ pub fn completed_tasks(days : u16, vault_folder : &path::Path) -> Result<(), err
         .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
     table.set_header(vec!["Task", "Completed"]);
 
-    for task in tasks {
-        if let Some(completed_date) = task.data.completed {
-            let time_diff = chrono::Local::now().naive_local() - completed_date;
-            if time_diff < chrono::Duration::days(i64::from(days)) && time_diff > chrono::Duration::zero() {
-                table.add_row(vec![
-                    task.data.name.clone(),
-                    completed_date.round_subsecs(0).to_string()
-                ]);
-            }
-        }
+    for (task, completed_date) in completed {
+        table.add_row(vec![
+            task.data.name.clone(),
+            completed_date.round_subsecs(0).to_string()
+        ]);
     }
 
     println!("{}", table);
@@ -32,25 +148,34 @@ pub fn completed_tasks(days : u16, vault_folder : &path::Path) -> Result<(), err
     Ok(())
 }
 
-pub fn time_per_tag(days : u16, vault_folder : &path::Path) -> Result<(), error::Error> {
+pub fn time_per_tag(period : Period, tag : Option<&str>, count_only : bool, output : args::OutputFormat, vault_folder : &path::Path) -> Result<(), error::Error> {
 
     let tasks = tasks::Task::load_all(vault_folder, true)?;
 
     let mut times = BTreeMap::<String, tasks::Duration>::new();
+    let mut untagged = tasks::Duration::zero();
 
     for task in &tasks {
         let mut time = tasks::Duration::zero();
 
         for entry in &task.data.time_entries {
-            if chrono::Local::now().naive_local().date() - entry.logged_date < chrono::Duration::days(i64::from(days)) {
+            if period.contains_date(entry.logged_date) {
                 time = time + entry.duration;
             }
         }
 
+        // Untagged time only belongs in the total, and only when not restricted to one tag.
+        if task.data.tags.is_empty() {
+            if tag.is_none() {
+                untagged = untagged + time;
+            }
+            continue;
+        }
+
         let tag_count = task.data.tags.len();
         let time_per_tag = time / tag_count;
 
-        for tag in &task.data.tags {
+        for tag in task.data.tags.iter().filter(|t| tag.map(|tag| tag == *t).unwrap_or(true)) {
             match times.get_mut(tag) {
                 Some(time) => {
                     *time = *time + time_per_tag;
@@ -62,6 +187,32 @@ pub fn time_per_tag(days : u16, vault_folder : &path::Path) -> Result<(), error:
         }
     }
 
+    let total_duration = times
+        .values()
+        .fold(untagged, |a, d| a + *d);
+
+    if count_only {
+        match output {
+            args::OutputFormat::Json => println!("{}", serde_json::to_string(&total_duration)?),
+            args::OutputFormat::Human | args::OutputFormat::Csv => println!("{}", total_duration),
+        }
+
+        return Ok(());
+    }
+
+    if output == args::OutputFormat::Json {
+        #[derive(serde::Serialize)]
+        struct Tracked<'a> {
+            tags : &'a BTreeMap<String, tasks::Duration>,
+            untagged : tasks::Duration,
+            total : tasks::Duration,
+        }
+
+        println!("{}", serde_json::to_string_pretty(&Tracked { tags : &times, untagged, total : total_duration })?);
+
+        return Ok(());
+    }
+
     let mut table = comfy_table::Table::new();
     table
         .load_preset(comfy_table::presets::UTF8_FULL)
@@ -69,8 +220,6 @@ pub fn time_per_tag(days : u16, vault_folder : &path::Path) -> Result<(), error:
         .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
     table.set_header(vec!["Tag", "Time"]);
 
-
-    let mut total_duration = tasks::Duration::zero();
     for (tag, duration) in times {
         table.add_row(
             vec![
@@ -78,6 +227,91 @@ pub fn time_per_tag(days : u16, vault_folder : &path::Path) -> Result<(), error:
                 duration.to_string(),
             ]
         );
+    }
+
+    if untagged != tasks::Duration::zero() {
+        table.add_row(
+            vec![
+                String::from("(untagged)"),
+                untagged.to_string(),
+            ]
+        );
+    }
+
+    table.add_row(
+        vec![
+            String::from("Total"),
+            total_duration.to_string(),
+        ]
+    );
+
+    println!("{}", table);
+
+    Ok(())
+}
+
+/// Shows the time tracked on each day of the period, including days with nothing tracked. Time
+/// is divided evenly between the tags of a task, so when only including one tag, tasks with
+/// several tags contribute part of their time.
+pub fn time_per_day(period : Period, tag : Option<&str>, output : args::OutputFormat, vault_folder : &path::Path) -> Result<(), error::Error> {
+
+    let tasks = tasks::Task::load_all(vault_folder, true)?;
+
+    let mut times = BTreeMap::<chrono::NaiveDate, tasks::Duration>::new();
+
+    let today = chrono::Local::now().naive_local().date();
+    if let Some(mut date) = period.start_date() {
+        while date <= today {
+            times.insert(date, tasks::Duration::zero());
+            date += chrono::Duration::days(1);
+        }
+    }
+
+    for task in &tasks {
+        // Without a tag all time is counted in full, including time on untagged tasks.
+        let divisor = match tag {
+            Some(tag) if task.data.tags.contains(tag) => task.data.tags.len(),
+            Some(_) => continue,
+            None => 1,
+        };
+
+        for entry in &task.data.time_entries {
+            if period.contains_date(entry.logged_date) {
+                let time = entry.duration / divisor;
+                let total = times.entry(entry.logged_date).or_insert_with(tasks::Duration::zero);
+                *total = *total + time;
+            }
+        }
+    }
+
+    if output == args::OutputFormat::Json {
+        #[derive(serde::Serialize)]
+        struct Day {
+            date : chrono::NaiveDate,
+            tracked : tasks::Duration,
+        }
+
+        let days : Vec<_> = times.into_iter().map(|(date, tracked)| Day { date, tracked }).collect();
+        println!("{}", serde_json::to_string_pretty(&days)?);
+
+        return Ok(());
+    }
+
+    let mut table = comfy_table::Table::new();
+    table
+        .load_preset(comfy_table::presets::UTF8_FULL)
+        .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
+        .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+    table.set_header(vec!["Date", "Time"]);
+
+    let mut total_duration = tasks::Duration::zero();
+    for (date, duration) in times {
+        table.add_row(
+            vec![
+                date.to_string(),
+                duration.to_string(),
+            ]
+        );
 
         total_duration = total_duration + duration;
     }
@@ -93,3 +327,385 @@ pub fn time_per_tag(days : u16, vault_folder : &path::Path) -> Result<(), error:
 
     Ok(())
 }
+
+pub fn time_entries(period : Period, tags : &[String], output : args::OutputFormat, vault_folder : &path::Path) -> Result<(), error::Error> {
+
+    let tasks = tasks::Task::load_all(vault_folder, true)?;
+
+    let mut entries = Vec::new();
+
+    for task in &tasks {
+        if !tags.is_empty() && !tags.iter().any(|t| task.data.tags.contains(t)) {
+            continue;
+        }
+
+        for entry in &task.data.time_entries {
+            if period.contains_date(entry.logged_date) {
+                entries.push((task, entry));
+            }
+        }
+    }
+
+    // Sort entries by date, most recent first.
+    entries.sort_by_key(|(_, entry)| cmp::Reverse(entry.logged_date));
+
+    if output == args::OutputFormat::Json {
+        #[derive(serde::Serialize)]
+        struct Entry<'a> {
+            date : chrono::NaiveDate,
+            id : tasks::Id,
+            task : &'a str,
+            duration : tasks::Duration,
+            message : Option<&'a str>,
+        }
+
+        let entries : Vec<_> = entries
+            .iter()
+            .map(|(task, entry)| Entry {
+                date : entry.logged_date,
+                id : task.data.id,
+                task : &task.data.name,
+                duration : entry.duration,
+                message : entry.message.as_deref(),
+            })
+            .collect();
+
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+
+        return Ok(());
+    }
+
+    let mut table = comfy_table::Table::new();
+    table
+        .load_preset(comfy_table::presets::UTF8_FULL)
+        .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
+        .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+    table.set_header(vec!["Date", "Task", "Duration", "Message"]);
+
+    for (task, entry) in entries {
+        table.add_row(
+            vec![
+                entry.logged_date.to_string(),
+                task.data.name.clone(),
+                entry.duration.to_string(),
+                entry.message.clone().unwrap_or_default(),
+            ]
+        );
+    }
+
+    println!("{}", table);
+
+    Ok(())
+}
+
+pub fn estimates(output : args::OutputFormat, vault_folder : &path::Path) -> Result<(), error::Error> {
+
+    let tasks = tasks::Task::load_all(vault_folder, true)?;
+
+    let incomplete = || tasks.iter().filter(|t| t.data.completed.is_none());
+    let mut estimated : Vec<_> = incomplete()
+        .filter_map(|t| t.data.estimate.map(|e| (t, e, tasks::TimeEntry::total(&t.data.time_entries))))
+        .collect();
+    estimated.sort_by_key(|(task, _, _)| task.data.id);
+    let excluded = incomplete().filter(|t| t.data.estimate.is_none()).count();
+
+    let total_estimate = estimated.iter().fold(tasks::Duration::zero(), |a, (_, e, _)| a + *e);
+    let total_tracked = estimated.iter().fold(tasks::Duration::zero(), |a, (_, _, t)| a + *t);
+
+    if output == args::OutputFormat::Json {
+        #[derive(serde::Serialize)]
+        struct Estimate<'a> {
+            id : tasks::Id,
+            name : &'a str,
+            estimate : tasks::Duration,
+            tracked : tasks::Duration,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Estimates<'a> {
+            tasks : Vec<Estimate<'a>>,
+            total_estimate : tasks::Duration,
+            total_tracked : tasks::Duration,
+            excluded : usize,
+        }
+
+        let tasks = estimated
+            .iter()
+            .map(|(task, estimate, tracked)| Estimate { id : task.data.id, name : &task.data.name, estimate : *estimate, tracked : *tracked })
+            .collect();
+
+        println!("{}", serde_json::to_string_pretty(&Estimates { tasks, total_estimate, total_tracked, excluded })?);
+
+        return Ok(());
+    }
+
+    let mut table = comfy_table::Table::new();
+    table
+        .load_preset(comfy_table::presets::UTF8_FULL)
+        .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
+        .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+    table.set_header(vec!["Task", "Estimate", "Tracked", "Difference"]);
+
+    for (task, estimate, tracked) in &estimated {
+        table.add_row(vec![
+            task.data.name.clone(),
+            estimate.to_string(),
+            tracked.to_string(),
+            format::over_under(*estimate, *tracked),
+        ]);
+    }
+
+    table.add_row(vec![
+        String::from("Total"),
+        total_estimate.to_string(),
+        total_tracked.to_string(),
+        format::over_under(total_estimate, total_tracked),
+    ]);
+
+    println!("{}", table);
+
+    if excluded != 0 {
+        println!("{} incomplete task{} without an estimate excluded", excluded, if excluded == 1 {""} else {"s"});
+    }
+
+    Ok(())
+}
+
+pub fn due(days : u16, output : args::OutputFormat, vault_folder : &path::Path) -> Result<(), error::Error> {
+
+    #[derive(Clone, Copy, PartialEq, Eq, serde::Serialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Bucket {
+        Overdue,
+        Today,
+        Upcoming,
+    }
+
+    let tasks = tasks::Task::load_all(vault_folder, true)?;
+
+    let now = chrono::Local::now().naive_local();
+    let horizon = now.date() + chrono::Duration::days(i64::from(days));
+
+    let mut due : Vec<_> = tasks
+        .iter()
+        .filter(|t| t.data.completed.is_none())
+        .filter_map(|t| {
+            let due = t.data.due?;
+            let bucket = if due < now {
+                Bucket::Overdue
+            }
+            else if due.date() == now.date() {
+                Bucket::Today
+            }
+            else if due.date() <= horizon {
+                Bucket::Upcoming
+            }
+            else {
+                return None;
+            };
+            Some((t, due, bucket))
+        })
+        .collect();
+    due.sort_by_key(|(_, due, _)| *due);
+
+    let count = |bucket| due.iter().filter(|(_, _, b)| *b == bucket).count();
+
+    if output == args::OutputFormat::Json {
+        #[derive(serde::Serialize)]
+        struct Due<'a> {
+            id : tasks::Id,
+            name : &'a str,
+            due : chrono::NaiveDateTime,
+            bucket : Bucket,
+        }
+
+        let due : Vec<_> = due
+            .iter()
+            .map(|(task, due, bucket)| Due { id : task.data.id, name : &task.data.name, due : *due, bucket : *bucket })
+            .collect();
+
+        println!("{}", serde_json::to_string_pretty(&due)?);
+
+        return Ok(());
+    }
+
+    println!(
+        "{} overdue, {} due today, {} due within {} day{}",
+        count(Bucket::Overdue),
+        count(Bucket::Today),
+        count(Bucket::Upcoming),
+        days,
+        if days == 1 {""} else {"s"},
+    );
+
+    if due.is_empty() {
+        return Ok(());
+    }
+
+    let mut table = comfy_table::Table::new();
+    table
+        .load_preset(comfy_table::presets::UTF8_FULL)
+        .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
+        .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+    table.set_header(vec!["Id", "Task", "Due"]);
+
+    for (task, due, _) in &due {
+        table.add_row(vec![
+            comfy_table::Cell::from(task.data.id),
+            comfy_table::Cell::from(task.data.name.clone()),
+            format::cell::due_date(due, true),
+        ]);
+    }
+
+    println!("{}", table);
+
+    Ok(())
+}
+
+pub fn burndown(days : u16, output : args::OutputFormat, vault_folder : &path::Path) -> Result<(), error::Error> {
+
+    /// Width of the longest bar in the chart.
+    const BAR_WIDTH : u64 = 40;
+
+    let tasks = tasks::Task::load_all(vault_folder, true)?;
+
+    let today = chrono::Local::now().naive_local().date();
+    let start = today - chrono::Duration::days(i64::from(days) - 1);
+
+    // Whether a task was open at some point on or after the start of the window.
+    let open_in_window = |task : &&tasks::Task| {
+        task.data.completed.map(|c| c.date() >= start).unwrap_or(true)
+    };
+
+    let estimated : Vec<_> = tasks.iter().filter(open_in_window).filter(|t| t.data.estimate.is_some()).collect();
+    let excluded = tasks.iter().filter(open_in_window).filter(|t| t.data.estimate.is_none()).count();
+
+    let mut remaining = Vec::with_capacity(usize::from(days));
+    let mut date = start;
+    while date <= today {
+        let total = estimated
+            .iter()
+            .filter(|t| t.data.created.date() <= date)
+            .filter(|t| t.data.completed.map(|c| c.date() > date).unwrap_or(true))
+            .map(|t| t.data.estimate.unwrap())
+            .fold(tasks::Duration::zero(), |a, d| a + d);
+
+        remaining.push((date, total));
+        date += chrono::Duration::days(1);
+    }
+
+    if output == args::OutputFormat::Json {
+        #[derive(serde::Serialize)]
+        struct Day {
+            date : chrono::NaiveDate,
+            remaining : tasks::Duration,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Burndown {
+            days : Vec<Day>,
+            excluded : usize,
+        }
+
+        let days = remaining.into_iter().map(|(date, remaining)| Day { date, remaining }).collect();
+        println!("{}", serde_json::to_string_pretty(&Burndown { days, excluded })?);
+
+        return Ok(());
+    }
+
+    let max_minutes = remaining.iter().map(|(_, d)| d.total_minutes()).max().unwrap_or(0);
+
+    for (date, total) in remaining {
+        let bar_length = (total.total_minutes() * BAR_WIDTH).checked_div(max_minutes).unwrap_or(0);
+        println!("{} {:<width$} {}", date, "█".repeat(bar_length as usize), total, width = BAR_WIDTH as usize);
+    }
+
+    if excluded != 0 {
+        println!("{} task{} without an estimate excluded", excluded, if excluded == 1 {""} else {"s"});
+    }
+
+    Ok(())
+}
+
+pub fn velocity(weeks : u16, output : args::OutputFormat, vault_folder : &path::Path) -> Result<(), error::Error> {
+    use chrono::Datelike;
+
+    /// Width of the longest bar in the chart.
+    const BAR_WIDTH : usize = 40;
+
+    let tasks = tasks::Task::load_all(vault_folder, true)?;
+
+    let today = chrono::Local::now().naive_local().date();
+    let this_week = today - chrono::Duration::days(i64::from(today.weekday().num_days_from_monday()));
+
+    // Completion counts by the Monday starting each week, so that empty weeks are still shown.
+    let mut counts = BTreeMap::<chrono::NaiveDate, usize>::new();
+    for week in 0..weeks {
+        counts.insert(this_week - chrono::Duration::weeks(i64::from(week)), 0);
+    }
+
+    for completed in tasks.iter().filter_map(|t| t.data.completed) {
+        let date = completed.date();
+        let monday = date - chrono::Duration::days(i64::from(date.weekday().num_days_from_monday()));
+        if let Some(count) = counts.get_mut(&monday) {
+            *count += 1;
+        }
+    }
+
+    let average = counts.values().sum::<usize>() as f64 / f64::from(weeks);
+
+    let week_name = |monday : &chrono::NaiveDate| {
+        let week = monday.iso_week();
+        format!("{}-W{:02}", week.year(), week.week())
+    };
+
+    if output == args::OutputFormat::Json {
+        #[derive(serde::Serialize)]
+        struct Week {
+            week : String,
+            start : chrono::NaiveDate,
+            completed : usize,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Velocity {
+            weeks : Vec<Week>,
+            average : f64,
+        }
+
+        let weeks = counts
+            .iter()
+            .map(|(start, completed)| Week { week : week_name(start), start : *start, completed : *completed })
+            .collect();
+
+        println!("{}", serde_json::to_string_pretty(&Velocity { weeks, average })?);
+
+        return Ok(());
+    }
+
+    let max = counts.values().copied().max().unwrap_or(0);
+
+    for (start, count) in &counts {
+        let bar_length = (count * BAR_WIDTH).checked_div(max).unwrap_or(0);
+        println!("{} ({}) {:<width$} {}", week_name(start), start, "█".repeat(bar_length), count, width = BAR_WIDTH);
+    }
+
+    println!("Average: {:.1} tasks per week", average);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn future_completion_is_within_recent_periods() {
+        let now = chrono::Local::now().naive_local();
+        let future = now + chrono::Duration::days(3);
+
+        assert!(Period::Days(7).contains(future));
+        assert!(Period::Since(now - chrono::Duration::days(1)).contains(future));
+        assert!(Period::weeks(1, chrono::Weekday::Mon).contains(future));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::args;
+use crate::error;
+use crate::tasks;
+use crate::state;
+use crate::config;
+use crate::format;
+use crate::journal;
+
+use std::path;
+use std::collections::BTreeMap;
+
+/// Renames a tag across all tasks, merging it into the new tag where a task already has both, and
+/// only saving the tasks which change. Returns the number of tasks updated.
+pub fn rename(old : &str, new : &str, vault_folder : &path::Path, state : &mut state::State, journal : &mut journal::Journal) -> Result<usize, error::Error> {
+    if old == new {
+        return Err(error::Error::Generic(format!("Tag {} is already named {}", old, new)));
+    }
+
+    let mut updated = 0;
+
+    for mut task in tasks::Task::load_all(vault_folder, false)? {
+        if task.data.tags.remove(old) {
+            journal.record(task.data.id)?;
+            task.data.tags.insert(String::from(new));
+            state.data.cache.insert(&task);
+            task.save()?;
+            updated += 1;
+        }
+    }
+
+    if updated == 0 {
+        Err(error::Error::Generic(format!("No tasks have the tag {}", old)))
+    }
+    else {
+        Ok(updated)
+    }
+}
+
+/// Lists all tags along with the number of tasks using each of them.
+pub fn list(tag_order : config::TagOrder, output : args::OutputFormat, vault_folder : &path::Path) -> Result<(), error::Error> {
+    let tasks = tasks::Task::load_all(vault_folder, true)?;
+
+    let mut counts = BTreeMap::<&String, usize>::new();
+    for tag in tasks.iter().flat_map(|t| t.data.tags.iter()) {
+        *counts.entry(tag).or_insert(0) += 1;
+    }
+
+    let mut counts : Vec<_> = counts.into_iter().collect();
+    if tag_order == config::TagOrder::Frequency {
+        // Stable sort, so ties stay alphabetical.
+        counts.sort_by(|(_, c1), (_, c2)| c2.cmp(c1));
+    }
+
+    if output == args::OutputFormat::Json {
+        #[derive(serde::Serialize)]
+        struct Tag<'a> {
+            name : &'a str,
+            tasks : usize,
+        }
+
+        let tags : Vec<_> = counts
+            .iter()
+            .map(|(name, tasks)| Tag { name, tasks : *tasks })
+            .collect();
+
+        println!("{}", serde_json::to_string_pretty(&tags)?);
+
+        return Ok(());
+    }
+
+    if counts.is_empty() {
+        println!("No tasks have tags, add them with: {}", format::command("toru new --tag <TAG>"));
+        return Ok(());
+    }
+
+    let mut table = comfy_table::Table::new();
+    table
+        .load_preset(comfy_table::presets::UTF8_FULL)
+        .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
+        .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+    table.set_header(vec!["Tag", "Tasks"]);
+
+    for (tag, count) in counts {
+        table.add_row(vec![tag.clone(), count.to_string()]);
+    }
+
+    println!("{}", table);
+
+    Ok(())
+}
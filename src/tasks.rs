@@ -1,19 +1,40 @@
+use crate::vcs;
+use crate::args;
 use crate::error;
+use crate::graph;
+use crate::config;
 use crate::state;
 use crate::format;
+use crate::prompt;
 
-use std::io;
 use std::fs;
 use std::str;
 use std::mem;
 use std::cmp;
 use std::path;
-use std::io::{Write, Seek};
-use std::collections::{HashSet, HashMap, BTreeSet};
+use std::io::Write;
+use std::collections::{HashMap, BTreeMap, BTreeSet};
 use chrono::SubsecRound;
 
 pub type Id = u64;
 
+/// Writes a file by writing to a temporary file alongside it and then renaming it over the
+/// original, so that an interrupted write leaves either the old or new contents.
+pub fn write_atomic(path : &path::Path, contents : &str) -> Result<(), error::Error> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = path::PathBuf::from(temp_path);
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    mem::drop(file);
+
+    fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
+
 pub struct Task {
     pub path : path::PathBuf,
     // This should only be None for a new task, in which case it should be written from the path.
@@ -25,14 +46,62 @@ pub struct Task {
 pub struct InternalTask {
     pub id : Id,
     pub name : String,
-    pub tags : HashSet<String>,
+    pub tags : BTreeSet<String>,
     pub dependencies : BTreeSet<Id>,
+    /// Task which this is a subtask of.
+    #[serde(default)]
+    pub parent : Option<Id>,
     pub priority : Priority,
     pub due : Option<chrono::NaiveDateTime>,
+    /// When work on the task is planned to start.
+    #[serde(default)]
+    pub scheduled : Option<chrono::NaiveDateTime>,
+    /// Estimated time required to complete the task.
+    #[serde(default)]
+    pub estimate : Option<Duration>,
+    /// Interval at which the task recurs, with a new task created when this one is completed.
+    #[serde(default)]
+    pub repeat : Option<Recurrence>,
     pub created : chrono::NaiveDateTime,
+    /// When the task was last saved, which is missing for tasks saved before this was recorded.
+    #[serde(default)]
+    pub modified : Option<chrono::NaiveDateTime>,
     pub completed : Option<chrono::NaiveDateTime>,
     pub info : Option<String>,
     pub time_entries : Vec<TimeEntry>,
+    /// Items which can be ticked off individually, kept last as they are written as tables.
+    #[serde(default, skip_serializing_if="Vec::is_empty")]
+    pub checklist : Vec<ChecklistItem>,
+}
+
+impl InternalTask {
+    /// Timestamps which should be in the past (from clock skew or editing) but are in the future,
+    /// along with the name of each.
+    pub fn future_timestamps(&self) -> Vec<(&'static str, chrono::NaiveDateTime)> {
+        let now = chrono::Local::now().naive_local();
+
+        [("created", Some(self.created)), ("completed", self.completed)]
+            .into_iter()
+            .filter_map(|(field, timestamp)| timestamp.filter(|t| *t > now).map(|t| (field, t)))
+            .collect()
+    }
+}
+
+/// Details of a task to be created, where everything other than the name is optional.
+#[derive(Default)]
+pub struct NewTask {
+    pub name : String,
+    pub info : Option<String>,
+    pub tags : Vec<String>,
+    pub dependencies : Vec<Id>,
+    /// Existing tasks which should depend on the new task.
+    pub blocks : Vec<Id>,
+    pub parent : Option<Id>,
+    pub priority : Option<Priority>,
+    pub due : Option<chrono::NaiveDateTime>,
+    pub scheduled : Option<chrono::NaiveDateTime>,
+    pub estimate : Option<Duration>,
+    pub repeat : Option<Recurrence>,
 }
 
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
@@ -46,7 +115,7 @@ pub enum Priority {
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Duration {
-    hours : u16,
+    hours : u32,
     minutes : u16,
 }
 
@@ -57,6 +126,18 @@ impl Duration {
             minutes : 0,
         }
     }
+
+    /// Creates a duration from a number of minutes, saturating if it is too large to represent.
+    pub fn from_minutes(minutes : u64) -> Self {
+        Self {
+            hours : u32::try_from(minutes / 60).unwrap_or(u32::MAX),
+            minutes : (minutes % 60) as u16,
+        }
+    }
+
+    pub fn total_minutes(&self) -> u64 {
+        u64::from(self.hours) * 60 + u64::from(self.minutes)
+    }
 }
 
 pub mod duration {
@@ -75,7 +156,7 @@ pub mod duration {
     }
 
     /// Deserialize from custom format HH:MM where MM is an integer between 0 and 59 inclusive, and
-    /// HH is some integer representable as a u16.
+    /// HH is some integer representable as a u32.
     /// The width of MM is not enforced for deserialization.
     impl<'de> serde::Deserialize<'de> for Duration {
         fn deserialize<D : serde::Deserializer<'de>>(deserializer : D) -> Result<Self, D::Error> {
@@ -93,7 +174,7 @@ pub mod duration {
     pub enum DurationRead {
         /// For when the number of minutes is not less than 60.
         Minutes,
-        /// For when either value cannot be parsed into a u16.
+        /// For when either value cannot be parsed (as a u32 for hours and a u16 for minutes).
         Range,
         /// For general formatting error (i.e. split at colon doesn't produce two values).
         General,
@@ -106,10 +187,10 @@ pub mod duration {
                     write!(f, "the number of minutes must be between 0 and 59 inclusive")
                 },
                 DurationRead::Range => {
-                    write!(f, "the number of hours and minutes must be representable as a u16")
+                    write!(f, "the number of hours must be representable as a u32 and minutes as a u16")
                 },
                 DurationRead::General => {
-                    write!(f, "duration must be in the format HH:MM where HH is any integer (representable as a u16) and MM is an integer between 0 and 59 inclusive")
+                    write!(f, "duration must be in the format HH:MM where HH is any integer (representable as a u32) and MM is an integer between 0 and 59 inclusive")
                 },
             }
         }
@@ -125,10 +206,10 @@ pub mod duration {
                     "the number of minutes to be an integer between 0 and 59 inclusive"
                 },
                 DurationRead::Range => {
-                    "the number of hours and minutes to be representable as a u16"
+                    "the number of hours to be representable as a u32 and minutes as a u16"
                 },
                 DurationRead::General => {
-                    "a duration in the format HH:MM where HH is any integer (representable as a u16) and MM is an integer between 0 and 59 inclusive"
+                    "a duration in the format HH:MM where HH is any integer (representable as a u32) and MM is an integer between 0 and 59 inclusive"
                 },
             }
         }
@@ -139,7 +220,7 @@ pub mod duration {
 
         fn from_str(s : &str) -> Result<Self, Self::Err> {
             if let &[h, m] = &s.split(':').collect::<Vec<&str>>()[..] {
-                if let (Ok(hours), Ok(minutes)) = (h.parse::<u16>(), m.parse::<u16>()) {
+                if let (Ok(hours), Ok(minutes)) = (h.parse::<u32>(), m.parse::<u16>()) {
                     if minutes < 60 {
                         Ok(Self {
                             hours,
@@ -165,25 +246,27 @@ pub mod duration {
 
         fn add(self, other : Self) -> Self::Output {
 
+            // Saturates rather than overflowing, which is only reachable with absurd durations.
             Self {
-                hours : self.hours + other.hours + (self.minutes + other.minutes) / 60,
+                hours : self.hours.saturating_add(other.hours).saturating_add(u32::from((self.minutes + other.minutes) / 60)),
                 minutes : (self.minutes + other.minutes) % 60,
             }
         }
     }
 
+    impl From<Duration> for chrono::Duration {
+        fn from(duration : Duration) -> Self {
+            chrono::Duration::minutes(i64::from(duration.hours) * 60 + i64::from(duration.minutes))
+        }
+    }
+
     impl ops::Div<usize> for Duration {
         type Output = Self;
 
         fn div(self, divisor : usize) -> Self::Output {
-            let total_mins = f64::from(self.hours * 60 + self.minutes);
-            let divided_mins = total_mins / (divisor as f64);
-            let divided_mins = divided_mins.round() as u16;
+            let divided_mins = (self.total_minutes() as f64) / (divisor as f64);
 
-            Self {
-                hours : divided_mins / 60,
-                minutes : divided_mins % 60,
-            }
+            Self::from_minutes(divided_mins.round() as u64)
         }
     }
 
@@ -195,6 +278,104 @@ pub mod duration {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+}
+
+impl Recurrence {
+    /// Advances the provided timestamp by one interval of the recurrence.
+    pub fn advance(&self, timestamp : chrono::NaiveDateTime) -> Option<chrono::NaiveDateTime> {
+        match *self {
+            Recurrence::Days(days) => timestamp.checked_add_signed(chrono::Duration::days(i64::from(days))),
+            Recurrence::Weeks(weeks) => timestamp.checked_add_signed(chrono::Duration::weeks(i64::from(weeks))),
+            Recurrence::Months(months) => timestamp.checked_add_months(chrono::Months::new(months)),
+            Recurrence::Years(years) => timestamp.checked_add_months(chrono::Months::new(years.checked_mul(12)?)),
+        }
+    }
+}
+
+pub mod recurrence {
+    use super::Recurrence;
+
+    use std::str;
+    use std::fmt;
+
+    /// Serialize to the same format as is accepted on the command line, such as 3d or 1mo.
+    impl serde::Serialize for Recurrence {
+        fn serialize<S : serde::Serializer>(&self, serializer : S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&self.to_string())
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for Recurrence {
+        fn deserialize<D : serde::Deserializer<'de>>(deserializer : D) -> Result<Self, D::Error> {
+            let raw = String::deserialize(deserializer)?;
+
+            use std::str::FromStr;
+            Self::from_str(&raw)
+            .map_err(|_| serde::de::Error::invalid_value(serde::de::Unexpected::Str(&raw), &RecurrenceRead::EXPECTED))
+        }
+    }
+
+    /// Error for when a recurrence cannot be parsed from a str.
+    #[derive(Debug)]
+    pub struct RecurrenceRead;
+
+    impl RecurrenceRead {
+        const EXPECTED : &'static str = "daily, weekly, monthly, yearly, or a positive integer followed by d, w, mo or y (such as 3d or 1mo)";
+    }
+
+    impl fmt::Display for RecurrenceRead {
+        fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "recurrence must be {}", RecurrenceRead::EXPECTED)
+        }
+    }
+
+    impl std::error::Error for RecurrenceRead { }
+
+    impl str::FromStr for Recurrence {
+        type Err = RecurrenceRead;
+
+        fn from_str(s : &str) -> Result<Self, Self::Err> {
+            match s {
+                "daily" => return Ok(Recurrence::Days(1)),
+                "weekly" => return Ok(Recurrence::Weeks(1)),
+                "monthly" => return Ok(Recurrence::Months(1)),
+                "yearly" => return Ok(Recurrence::Years(1)),
+                _ => (),
+            }
+
+            let split = s.find(|c : char| !c.is_ascii_digit()).ok_or(RecurrenceRead)?;
+            let (count, unit) = s.split_at(split);
+            let count = count.parse::<u32>().ok().filter(|c| *c > 0).ok_or(RecurrenceRead)?;
+
+            match unit {
+                "d" => Ok(Recurrence::Days(count)),
+                "w" => Ok(Recurrence::Weeks(count)),
+                "mo" => Ok(Recurrence::Months(count)),
+                "y" => Ok(Recurrence::Years(count)),
+                _ => Err(RecurrenceRead),
+            }
+        }
+    }
+
+    /// Same display format as serialization.
+    impl fmt::Display for Recurrence {
+        fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Recurrence::Days(days) => write!(f, "{}d", days),
+                Recurrence::Weeks(weeks) => write!(f, "{}w", weeks),
+                Recurrence::Months(months) => write!(f, "{}mo", months),
+                Recurrence::Years(years) => write!(f, "{}y", years),
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TimeEntry {
     pub logged_date : chrono::NaiveDate,
@@ -202,6 +383,12 @@ pub struct TimeEntry {
     pub duration : Duration,
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ChecklistItem {
+    pub text : String,
+    pub done : bool,
+}
+
 impl TimeEntry {
     /// Adds up the times from a collection of time entries.
     pub fn total(entries : &[TimeEntry]) -> Duration {
@@ -222,9 +409,62 @@ impl TimeEntry {
     }
 }
 
+impl InternalTask {
+    /// When the task was last modified, which is when it was created if it hasn't been recorded.
+    pub fn modified(&self) -> chrono::NaiveDateTime {
+        self.modified.unwrap_or(self.created)
+    }
+}
+
+/// The fields of a task needed for listing, cached in the state file to avoid loading every task.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Metadata {
+    pub id : Id,
+    pub name : String,
+    pub tags : BTreeSet<String>,
+    pub parent : Option<Id>,
+    pub priority : Priority,
+    pub due : Option<chrono::NaiveDateTime>,
+    pub scheduled : Option<chrono::NaiveDateTime>,
+    pub estimate : Option<Duration>,
+    pub created : chrono::NaiveDateTime,
+    pub modified : chrono::NaiveDateTime,
+    pub completed : Option<chrono::NaiveDateTime>,
+    pub tracked : Duration,
+    /// Number of checklist items which are done, and the total number of items.
+    pub checklist : (usize, usize),
+}
+
+impl From<&Task> for Metadata {
+    fn from(task : &Task) -> Self {
+        Self {
+            id : task.data.id,
+            name : task.data.name.clone(),
+            tags : task.data.tags.clone(),
+            parent : task.data.parent,
+            priority : task.data.priority.clone(),
+            due : task.data.due,
+            scheduled : task.data.scheduled,
+            estimate : task.data.estimate,
+            created : task.data.created,
+            modified : task.data.modified(),
+            completed : task.data.completed,
+            tracked : TimeEntry::total(&task.data.time_entries),
+            checklist : (task.data.checklist.iter().filter(|i| i.done).count(), task.data.checklist.len()),
+        }
+    }
+}
+
 impl Task {
     /// Creates a new task from the input data.
-    pub fn new(name : String, info : Option<String>, tags : Vec<String>, dependencies : Vec<Id>, priority : Option<Priority>, due : Option<chrono::NaiveDateTime>, vault_folder : &path::Path, state : &mut state::State) -> Result<Id, error::Error> {
+    pub fn new(new_task : NewTask, vault_folder : &path::Path, state : &mut state::State) -> Result<Id, error::Error> {
+        let NewTask { name, info, tags, dependencies, blocks, parent, priority, due, scheduled, estimate, repeat } = new_task;
+
+        if let Some(parent) = parent {
+            if !state.data.deps.contains_node(parent) {
+                return Err(error::Error::Generic(format!("No task with an ID of {} exists", format::id(parent))));
+            }
+        }
 
         // Update the state with the new next Id.
         let id = state.data.next_id;
@@ -245,16 +485,38 @@ impl Task {
             }
         }
 
+        // Adding reverse edges for the tasks which the new task blocks.
+        if !blocks.is_empty() {
+            for dependent in &blocks {
+                if state.data.deps.contains_node(*dependent) {
+                    state.data.deps.insert_edge(*dependent, id)?;
+                }
+                else {
+                    return Err(error::Error::Generic(format!("No task with an ID of {} exists", format::id(*dependent))));
+                }
+            }
+
+            if let Some(cycle) = state.data.deps.find_cycle() {
+                return Err(error::Error::Generic(format!("Task creation aborted due to circular dependency: {}", graph::format_cycle(&cycle))));
+            }
+        }
+
         let data = InternalTask {
             id,
             name,
             info,
             tags : tags.into_iter().collect(),
             dependencies : dependencies.into_iter().collect(),
+            parent,
             priority : priority.unwrap_or_default(),
             due,
+            scheduled,
+            estimate,
+            repeat,
             time_entries : Vec::new(),
+            checklist : Vec::new(),
             created : chrono::Local::now().naive_local(),
+            modified : None,
             completed : None,
         };
 
@@ -266,11 +528,236 @@ impl Task {
             data,
         };
 
+        state.data.cache.insert(&task);
         task.save()?;
 
+        for dependent in blocks {
+            let mut task = Task::load(dependent, vault_folder, false)?;
+            task.data.dependencies.insert(id);
+            state.data.cache.insert(&task);
+            task.save()?;
+        }
+
         Ok(id)
     }
 
+    /// Creates an incomplete copy of a task with no time entries. If `with_deps` is set, all of the
+    /// task's nested dependencies are also copied, with the copies depending on each other rather
+    /// than the originals. The copy of the specified task is given `name` if provided. Returns the
+    /// ID of the copy of the specified task.
+    pub fn duplicate(id : Id, name : Option<String>, with_deps : bool, vault_folder : &path::Path, state : &mut state::State) -> Result<Id, error::Error> {
+
+        let to_copy = if with_deps {
+            if let Some(cycle) = state.data.deps.find_cycle() {
+                return Err(error::Error::Generic(format!("Cannot copy dependencies due to circular dependency: {}", graph::format_cycle(&cycle))));
+            }
+            state.data.deps.topological_subtree(id)
+        }
+        else {
+            vec![id]
+        };
+
+        // Map from original IDs to the IDs of their copies.
+        let mut copies = HashMap::new();
+
+        let mut name = name;
+
+        // Dependencies are always copied before their dependents, so the new IDs are available.
+        for original_id in to_copy {
+            let mut original = Task::load(original_id, vault_folder, true)?;
+
+            if original_id == id {
+                if let Some(name) = name.take() {
+                    original.data.name = name;
+                }
+            }
+
+            let id = state.data.next_id;
+            state.data.next_id += 1;
+
+            let dependencies : BTreeSet<Id> = original.data.dependencies
+                .iter()
+                .map(|d| *copies.get(d).unwrap_or(d))
+                .collect();
+
+            state.data.deps.insert_node(id);
+            for dependency in &dependencies {
+                state.data.deps.insert_edge(id, *dependency)?;
+            }
+
+            let data = InternalTask {
+                id,
+                dependencies,
+                created : chrono::Local::now().naive_local(),
+                modified : None,
+                completed : None,
+                time_entries : Vec::new(),
+                checklist : unchecked(&original.data.checklist),
+                ..original.data
+            };
+
+            state.data.index.insert(data.name.clone(), id);
+
+            let task = Task {
+                path : vault_folder.join("tasks").join(format!("{}.toml", id)),
+                file : None,
+                data,
+            };
+
+            state.data.cache.insert(&task);
+            task.save()?;
+
+            copies.insert(original_id, id);
+        }
+
+        Ok(copies[&id])
+    }
+
+    /// Creates the next occurrence of a recurring task, with the due date advanced by the
+    /// recurrence interval (from the completion time if there was no due date). Returns the ID of
+    /// the new task, or None if the task doesn't repeat.
+    pub fn recur(&self, completed : chrono::NaiveDateTime, vault_folder : &path::Path, state : &mut state::State) -> Result<Option<Id>, error::Error> {
+        let repeat = match self.data.repeat {
+            Some(repeat) => repeat,
+            None => return Ok(None),
+        };
+
+        let due = repeat
+            .advance(self.data.due.unwrap_or(completed))
+            .ok_or_else(|| error::Error::Generic(format!("Next due date for task {} is out of range", format::id(self.data.id))))?;
+
+        let next = NewTask {
+            name : self.data.name.clone(),
+            tags : self.data.tags.iter().cloned().collect(),
+            parent : self.data.parent,
+            priority : Some(self.data.priority.clone()),
+            due : Some(due),
+            scheduled : self.data.scheduled.and_then(|scheduled| repeat.advance(scheduled)),
+            estimate : self.data.estimate,
+            repeat : Some(repeat),
+            ..Default::default()
+        };
+
+        let id = Task::new(next, vault_folder, state)?;
+
+        if !self.data.checklist.is_empty() {
+            let mut next = Task::load(id, vault_folder, false)?;
+            next.data.checklist = unchecked(&self.data.checklist);
+            state.data.cache.insert(&next);
+            next.save()?;
+        }
+
+        Ok(Some(id))
+    }
+
+    /// Gets the time entries sorted by date, as they are displayed, along with their position in
+    /// the task's list of entries.
+    pub fn sorted_time_entries(&self) -> Vec<(usize, &TimeEntry)> {
+        let mut entries : Vec<_> = self.data.time_entries.iter().enumerate().collect();
+        entries.sort_by(|(_, e1), (_, e2)| e1.logged_date.cmp(&e2.logged_date));
+        entries
+    }
+
+    /// Removes the time entry at the given (one-based) index, as numbered when the task is
+    /// displayed, returning the removed entry.
+    pub fn untrack(&mut self, index : usize) -> Result<TimeEntry, error::Error> {
+        if self.data.time_entries.is_empty() {
+            return Err(error::Error::Generic(format!("Task {} has no time entries", format::id(self.data.id))));
+        }
+
+        let position = index
+            .checked_sub(1)
+            .and_then(|i| self.sorted_time_entries().get(i).map(|(position, _)| *position))
+            .ok_or_else(|| error::Error::Generic(format!("Task {} has no time entry {}, expected an index from 1 to {}", format::id(self.data.id), index, self.data.time_entries.len())))?;
+
+        Ok(self.data.time_entries.remove(position))
+    }
+
+    /// Gets the IDs and names of the task's nested dependencies which are incomplete, ordered by
+    /// ID.
+    pub fn incomplete_dependencies(&self, vault_folder : &path::Path, state : &state::State) -> Result<Vec<(Id, String)>, error::Error> {
+        let mut incomplete = Vec::new();
+
+        for dependency in state.data.deps.get_nested_deps(self.data.id)? {
+            let (name, completed) = match state.data.cache.get(dependency) {
+                Some(metadata) => (metadata.name.clone(), metadata.completed.is_some()),
+                None => {
+                    let task = Task::load(dependency, vault_folder, true)?;
+                    (task.data.name, task.data.completed.is_some())
+                }
+            };
+
+            if !completed {
+                incomplete.push((dependency, name));
+            }
+        }
+
+        incomplete.sort();
+
+        Ok(incomplete)
+    }
+
+    /// Adds an item to the end of the task's checklist, returning its (one-based) index.
+    pub fn add_checklist_item(&mut self, text : &str) -> Result<usize, error::Error> {
+        if text.trim().is_empty() {
+            return Err(error::Error::Generic(String::from("Checklist items must not be empty")));
+        }
+
+        self.data.checklist.push(ChecklistItem { text : String::from(text.trim()), done : false });
+
+        Ok(self.data.checklist.len())
+    }
+
+    /// Ticks or unticks the checklist item at the given (one-based) index, as numbered when the
+    /// task is displayed, returning the updated item.
+    pub fn toggle_checklist_item(&mut self, index : usize) -> Result<&ChecklistItem, error::Error> {
+        if self.data.checklist.is_empty() {
+            return Err(error::Error::Generic(format!("Task {} has no checklist items", format::id(self.data.id))));
+        }
+
+        let count = self.data.checklist.len();
+        let item = index
+            .checked_sub(1)
+            .and_then(|i| self.data.checklist.get_mut(i))
+            .ok_or_else(|| error::Error::Generic(format!("Task {} has no checklist item {}, expected an index from 1 to {}", format::id(self.data.id), index, count)))?;
+
+        item.done = !item.done;
+
+        Ok(item)
+    }
+
+    /// Appends a timestamped note to the end of the task's info. Notes which are only whitespace
+    /// are ignored.
+    pub fn append_note(&mut self, note : &str, timestamp : chrono::NaiveDateTime) {
+        if note.trim().is_empty() {
+            return;
+        }
+
+        let note = format!("[{}] {}", timestamp.format("%Y-%m-%d %H:%M"), note.trim());
+
+        self.data.info = match self.data.info.take().filter(|info| !info.trim().is_empty()) {
+            Some(info) => Some(format!("{}\n\n{}", info.trim_end(), note)),
+            None => Some(note),
+        };
+    }
+
+    /// Adds a time entry to a task. If the task is complete and `force` is not set, a warning is
+    /// printed instead. Returns whether the entry was added.
+    pub fn track(id : Id, entry : TimeEntry, force : bool, vault_folder : &path::Path, state : &mut state::State) -> Result<bool, error::Error> {
+        let mut task = Task::load(id, vault_folder, false)?;
+
+        if task.data.completed.is_some() && !force {
+            println!("{} Task {} is already complete, use {} to track time against it anyway", format::warning("Warning:"), format::id(id), format::command("--force"));
+            Ok(false)
+        }
+        else {
+            task.data.time_entries.push(entry);
+            state.data.cache.insert(&task);
+            task.save()?;
+            Ok(true)
+        }
+    }
+
     /// Loads a task directly from its path, for use with the temporary edit file.
     pub fn load_direct(path : path::PathBuf, read_only : bool) -> Result<Self, error::Error> {
         let file_contents = fs::read_to_string(&path)?;
@@ -302,7 +789,7 @@ impl Task {
     }
 
     /// Get an iterator over the IDs of tasks in a vault.
-    fn id_iter(vault_folder : &path::Path) -> impl Iterator<Item = u64> {
+    pub fn id_iter(vault_folder : &path::Path) -> impl Iterator<Item = u64> {
         fs::read_dir(vault_folder.join("tasks"))
         .unwrap()
         .map(|entry| entry.unwrap().path())
@@ -347,8 +834,20 @@ impl Task {
         }
     }
 
-    /// Saves the in memory task data to the corresponding file.
-    pub fn save(self) -> Result<(), error::Error> {
+    /// Saves the in memory task data to the corresponding file, updating when it was modified, and
+    /// warning about any creation or completion date in the future.
+    pub fn save(mut self) -> Result<(), error::Error> {
+        for (field, timestamp) in self.data.future_timestamps() {
+            println!("{} The {} date {} is in the future", format::warning("Warning:"), field, timestamp.round_subsecs(0));
+        }
+
+        self.data.modified = Some(chrono::Local::now().naive_local());
+        self.write()
+    }
+
+    /// Saves the in memory task data without updating when it was modified, for rewrites which
+    /// don't change the task.
+    pub fn write(self) -> Result<(), error::Error> {
 
         // Enforce any additional invariants which need to be checked for both edits and now tasks
         // at the point of save.
@@ -367,24 +866,149 @@ impl Task {
 
         let file_contents = toml::to_string(&data)?;
 
-        // Check if the file exists, if not it is a new task and the file must be written from the
-        // path.
-        match file {
-            Some(mut file) => {
-                file.set_len(0)?;
-                file.seek(io::SeekFrom::Start(0))?;
-                file.write_all(file_contents.as_bytes())?;
-            },
-            None => {
-                fs::write(path, file_contents.as_bytes())?;
+        // The file is replaced rather than written to, so the handle (which is None for a new
+        // task) is closed first.
+        mem::drop(file);
+
+        write_atomic(&path, &file_contents)
+    }
+
+    /// Deletes a task along with its entries in the index and dependency graph, and removes it from
+    /// the dependencies of other tasks. Returns the name of the deleted task.
+    pub fn remove(id : Id, mode : config::DeleteMode, vault_folder : &path::Path, state : &mut state::State) -> Result<String, error::Error> {
+        let task = Task::load(id, vault_folder, false)?;
+        let name = task.data.name.clone();
+        // Removing the file first, so that a failed removal leaves other tasks untouched.
+        task.delete(mode, vault_folder)?;
+        state.data.index.remove(name.clone(), id);
+        state.data.cache.remove(id);
+        // Removing the task from others which list it as a dependency.
+        if let (true, dependents) = state.data.deps.remove_node(id) {
+            for dependent in dependents {
+                let mut task = Task::load(dependent, vault_folder, false)?;
+                task.data.dependencies.remove(&id);
+                state.data.cache.insert(&task);
+                task.save()?;
             }
         }
 
-        Ok(())
+        Ok(name)
+    }
+
+    /// Gets the IDs of all subtasks of a task, direct and indirect, with each subtask before its
+    /// own subtasks.
+    pub fn subtasks(id : Id, state : &state::State) -> Vec<Id> {
+        let mut subtasks = Vec::new();
+        let mut queue = state.data.cache.children(id);
+
+        while let Some(subtask) = queue.first().copied() {
+            queue.remove(0);
+            // Guarding against a cycle of parents, which can only come from editing files by hand.
+            if subtask != id && !subtasks.contains(&subtask) {
+                subtasks.push(subtask);
+                queue.extend(state.data.cache.children(subtask));
+            }
+        }
+
+        subtasks
     }
 
-    /// Deletes the task.
-    pub fn delete(self) -> Result<(), error::Error> {
+    /// Marks each of the tasks which is incomplete as completed at the given time, returning how
+    /// many were changed.
+    pub fn complete_all(ids : &[Id], completed : chrono::NaiveDateTime, vault_folder : &path::Path, state : &mut state::State) -> Result<usize, error::Error> {
+        let mut count = 0;
+
+        for id in ids {
+            let mut task = Task::load(*id, vault_folder, false)?;
+            if task.data.completed.is_none() {
+                task.data.completed = Some(completed);
+                state.data.cache.insert(&task);
+                task.save()?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Moves a task to another vault where it is given a new ID, keeping only dependencies which
+    /// match exactly one task of the same name in the destination. If the original can't be
+    /// removed, the moved task file is removed again. Returns the new ID.
+    pub fn move_vault(id : Id, mode : config::DeleteMode, vault_folder : &path::Path, state : &mut state::State, destination_folder : &path::Path, mut destination_state : state::State) -> Result<Id, error::Error> {
+        if state.data.timer.as_ref().map(|t| t.id) == Some(id) {
+            return Err(error::Error::Generic(format!("Task {} has a running timer, stop it first with: {}", format::id(id), format::command("toru track stop"))));
+        }
+
+        if !state.data.cache.children(id).is_empty() {
+            return Err(error::Error::Generic(format!("Task {} has subtasks, which must be moved or deleted first", format::id(id))));
+        }
+
+        let original = Task::load(id, vault_folder, true)?;
+
+        if let Some(parent) = original.data.parent {
+            println!("{} Parent task {} is not moved, so the task will no longer be a subtask", format::warning("Warning:"), format::id(parent));
+        }
+
+        // IDs are specific to each vault, so dependencies are matched by name instead.
+        let mut dependencies = BTreeSet::new();
+        for dependency in &original.data.dependencies {
+            let name = match state.data.cache.get(*dependency) {
+                Some(metadata) => metadata.name.clone(),
+                None => Task::load(*dependency, vault_folder, true)?.data.name,
+            };
+            match destination_state.data.index.ids(&name) {
+                [destination_id] => {
+                    dependencies.insert(*destination_id);
+                },
+                [] => {
+                    println!("{} Dependency {} has no task of the same name in the destination vault and was dropped", format::warning("Warning:"), format::task(&name));
+                },
+                _ => {
+                    println!("{} Dependency {} matches multiple tasks in the destination vault and was dropped", format::warning("Warning:"), format::task(&name));
+                },
+            }
+        }
+
+        let new_id = destination_state.data.next_id;
+        destination_state.data.next_id += 1;
+
+        // As nothing depends on the new task, these edges cannot create a cycle.
+        destination_state.data.deps.insert_node(new_id);
+        for dependency in &dependencies {
+            destination_state.data.deps.insert_edge(new_id, *dependency)?;
+        }
+
+        let data = InternalTask {
+            id : new_id,
+            dependencies,
+            parent : None,
+            ..original.data
+        };
+
+        destination_state.data.index.insert(data.name.clone(), new_id);
+
+        let task = Task {
+            path : destination_folder.join("tasks").join(format!("{}.toml", new_id)),
+            file : None,
+            data,
+        };
+
+        destination_state.data.cache.insert(&task);
+        let destination_path = task.path.clone();
+        task.save()?;
+
+        if let Err(error) = Task::remove(id, mode, vault_folder, state) {
+            fs::remove_file(&destination_path)?;
+            return Err(error);
+        }
+
+        destination_state.save()?;
+
+        Ok(new_id)
+    }
+
+    /// Deletes the task, removing the file according to the delete mode.
+    pub fn delete(self, mode : config::DeleteMode, vault_folder : &path::Path) -> Result<(), error::Error> {
         let Self {
             path,
             file,
@@ -392,14 +1016,74 @@ impl Task {
         } = self;
 
         mem::drop(file);
-        trash::delete(&path)?;
+
+        match mode {
+            config::DeleteMode::Trash => {
+                trash::delete(&path)?;
+            },
+            config::DeleteMode::Remove => {
+                fs::remove_file(&path)?;
+            },
+            config::DeleteMode::GitRm => {
+                let args = vec![String::from("rm"), String::from("--quiet"), path.display().to_string()];
+                vcs::command(args, vcs::Vcs::Git, vault_folder)?;
+
+                // Git reports its own errors, so just make sure the file was actually removed.
+                if path.exists() {
+                    return Err(error::Error::Generic(format!("Failed to remove {} with git, check that it is tracked", format::file(&path.display().to_string()))));
+                }
+            },
+        }
 
         Ok(())
     }
 
     /// Displays a task to the terminal.
-    pub fn display(&self, vault_folder : &path::Path, state : &state::State) -> Result<(), error::Error> {
-        
+    pub fn display(&self, output : args::OutputFormat, vault_folder : &path::Path, state : &state::State, detailed : bool) -> Result<(), error::Error> {
+
+        if output == args::OutputFormat::Json {
+            /// A task in the dependency tree, with its dependencies given by ID.
+            #[derive(serde::Serialize)]
+            struct Node<'a> {
+                name : Option<&'a str>,
+                completed : Option<chrono::NaiveDateTime>,
+                dependencies : Vec<Id>,
+            }
+
+            /// The task along with the total time tracked and all of its nested dependencies as
+            /// an adjacency list.
+            #[derive(serde::Serialize)]
+            struct Detailed<'a> {
+                #[serde(flatten)]
+                task : &'a InternalTask,
+                tracked : Duration,
+                dependency_tree : BTreeMap<Id, Node<'a>>,
+            }
+
+            let dependency_tree = state.data.deps
+                .get_nested_deps(self.data.id)?
+                .into_iter()
+                .map(|id| {
+                    let metadata = state.data.cache.get(id);
+                    let node = Node {
+                        name : metadata.map(|m| m.name.as_str()),
+                        completed : metadata.and_then(|m| m.completed),
+                        dependencies : state.data.deps.edges.get(&id).into_iter().flatten().copied().collect(),
+                    };
+                    (id, node)
+                })
+                .collect();
+
+            let detailed = Detailed {
+                task : &self.data,
+                tracked : TimeEntry::total(&self.data.time_entries),
+                dependency_tree,
+            };
+
+            println!("{}", serde_json::to_string_pretty(&detailed)?);
+            return Ok(());
+        }
+
         /// Displays a line of hyphens of a specified length.
         fn line(len : usize) {
             for _ in 0..len {
@@ -418,14 +1102,35 @@ impl Task {
         line(heading_length);
 
         println!("Priority:     {}", format::priority(&self.data.priority));
-        println!("Tags:         [{}]", format::hash_set(&self.data.tags)?);
+        println!("Tags:         [{}]", format::coloured_tags(&self.data.tags, None));
         println!("Created:      {}", self.data.created.round_subsecs(0));
+        println!("Modified:     {}", self.data.modified().round_subsecs(0));
         
         if let Some(due) = self.data.due {
             let due = format::due_date(&due, self.data.completed.is_none());
             println!("Due:          {}", due);
         }
 
+        if let Some(scheduled) = self.data.scheduled {
+            println!("Scheduled:    {}", scheduled.round_subsecs(0));
+        }
+
+        if let Some(estimate) = self.data.estimate {
+            let tracked = TimeEntry::total(&self.data.time_entries);
+            println!("Estimate:     {} ({})", estimate, format::over_under(estimate, tracked));
+        }
+
+        if let Some(repeat) = self.data.repeat {
+            println!("Repeats:      every {}", repeat);
+        }
+
+        if let Some(parent) = self.data.parent {
+            match Task::load(parent, vault_folder, true) {
+                Ok(parent) => println!("Parent:       {} (ID: {})", format::task(&parent.data.name), format::id(parent.data.id)),
+                Err(_) => println!("Parent:       {} (missing)", format::id(parent)),
+            }
+        }
+
         if let Some(mut info) = self.data.info.clone() {
             let mut max_line_width = 0;
             println!("Info:");
@@ -441,18 +1146,26 @@ impl Task {
             }
         }
 
+        if !self.data.checklist.is_empty() {
+            let done = self.data.checklist.iter().filter(|i| i.done).count();
+            println!("Checklist:    {}/{}", done, self.data.checklist.len());
+
+            for (index, item) in self.data.checklist.iter().enumerate() {
+                println!("    {}. [{}] {}", index + 1, if item.done {"x"} else {" "}, item.text);
+            }
+        }
+
         // Display tracked time.
         if !self.data.time_entries.is_empty() {
 
-            let mut entries = self.data.time_entries.clone();
-            // Sort time entries by date.
-            entries.sort_by(|e1, e2| e1.logged_date.cmp(&e2.logged_date));
+            let entries = self.sorted_time_entries();
 
             let mut total = Duration::zero();
             let mut lines = Vec::with_capacity(entries.len());
-            for entry in &entries {
+            for (index, (_, entry)) in entries.iter().enumerate() {
                 lines.push(format!(
-                    "    {} [{}] {}",
+                    "    {}. {} [{}] {}",
+                    index + 1,
                     entry.duration,
                     entry.logged_date,
                     entry.message.as_ref().unwrap_or(&String::new())
@@ -470,7 +1183,14 @@ impl Task {
         if !self.data.dependencies.is_empty() {
 
             println!("Dependencies:");
-            format::dependencies(self.data.id, vault_folder, &state.data.deps)?;
+            format::dependencies(self.data.id, vault_folder, &state.data.deps, detailed)?;
+        }
+
+        // Display subtasks as tree.
+        if !state.data.cache.children(self.data.id).is_empty() {
+
+            println!("Subtasks:");
+            format::subtasks(self.data.id, vault_folder, &state.data.cache, detailed)?;
         }
         
         Ok(())
@@ -488,3 +1208,305 @@ pub fn compare_due_dates<T : Ord>(first : &Option<T>, second : &Option<T>) -> cm
     }
 }
 
+/// Copies a checklist with every item unticked, for new tasks based on an existing one.
+fn unchecked(checklist : &[ChecklistItem]) -> Vec<ChecklistItem> {
+    checklist
+        .iter()
+        .map(|item| ChecklistItem { text : item.text.clone(), done : false })
+        .collect()
+}
+
+/// Roughly 100 years, for catching mistyped years in due dates.
+const DISTANT_DUE_DAYS : i64 = 100 * 365;
+
+/// Prints a warning if the due date is implausibly far from now, which usually indicates a typo in
+/// the year, returning whether it is.
+pub fn warn_if_distant(due : &chrono::NaiveDateTime) -> bool {
+    let distance = *due - chrono::Local::now().naive_local();
+    let distant = distance.num_days().abs() > DISTANT_DUE_DAYS;
+
+    if distant {
+        println!("{} Due date {} is more than 100 years from now", format::warning("Warning:"), due.round_subsecs(0));
+    }
+
+    distant
+}
+
+/// Warns if the due date is implausibly far from now, and asks whether to keep it unless
+/// confirmation is skipped or there is no one to answer, giving an error if it is declined.
+pub fn confirm_due(due : &chrono::NaiveDateTime, yes : bool) -> Result<(), error::Error> {
+    if warn_if_distant(due) && !yes && prompt::is_interactive() && !prompt::confirm("Keep this due date?")? {
+        Err(error::Error::Generic(String::from("Aborted as the due date was not confirmed")))
+    }
+    else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    /// A task with only the fields which have always been present.
+    fn sample() -> InternalTask {
+        toml::from_str(r#"
+            id = 3
+            name = "sample"
+            tags = ["work"]
+            dependencies = [1]
+            priority = "Medium"
+            created = "2023-01-02T09:30:00"
+            time_entries = []
+        "#).unwrap()
+    }
+
+    /// Writes a task out as it is saved to a file and reads it back.
+    fn round_trip(data : &InternalTask) -> InternalTask {
+        toml::from_str(&toml::to_string(data).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn new_fields_default_when_missing() {
+        let data = sample();
+
+        assert_eq!(data.parent, None);
+        assert_eq!(data.scheduled, None);
+        assert_eq!(data.estimate, None);
+        assert_eq!(data.repeat, None);
+        assert_eq!(data.modified, None);
+        assert!(data.checklist.is_empty());
+    }
+
+    #[test]
+    fn parent_round_trips() {
+        let mut data = sample();
+        data.parent = Some(2);
+
+        assert_eq!(round_trip(&data).parent, Some(2));
+    }
+
+    #[test]
+    fn scheduled_round_trips() {
+        let scheduled = chrono::NaiveDate::from_ymd_opt(2023, 2, 1).unwrap().and_hms_opt(8, 0, 0).unwrap();
+        let mut data = sample();
+        data.scheduled = Some(scheduled);
+
+        assert_eq!(round_trip(&data).scheduled, Some(scheduled));
+    }
+
+    #[test]
+    fn estimate_round_trips() {
+        let mut data = sample();
+        data.estimate = Some(Duration::from_minutes(150));
+
+        assert_eq!(round_trip(&data).estimate, Some(Duration::from_minutes(150)));
+    }
+
+    #[test]
+    fn repeat_round_trips() {
+        for repeat in [Recurrence::Days(3), Recurrence::Weeks(2), Recurrence::Months(1), Recurrence::Years(1)] {
+            let mut data = sample();
+            data.repeat = Some(repeat);
+
+            assert_eq!(round_trip(&data).repeat, Some(repeat));
+        }
+    }
+
+    #[test]
+    fn modified_round_trips() {
+        let modified = chrono::NaiveDate::from_ymd_opt(2023, 3, 4).unwrap().and_hms_opt(17, 45, 12).unwrap();
+        let mut data = sample();
+        data.modified = Some(modified);
+
+        assert_eq!(round_trip(&data).modified, Some(modified));
+    }
+
+    #[test]
+    fn checklist_round_trips() {
+        let mut data = sample();
+        data.checklist = vec![
+            ChecklistItem { text : String::from("first"), done : true },
+            ChecklistItem { text : String::from("second"), done : false },
+        ];
+
+        let items : Vec<_> = round_trip(&data).checklist.into_iter().map(|i| (i.text, i.done)).collect();
+        assert_eq!(items, vec![(String::from("first"), true), (String::from("second"), false)]);
+    }
+
+    #[test]
+    fn durations_sum_past_u16_hours() {
+        // 70,000 hours in total, which would overflow a u16 count of hours.
+        let entries : Vec<_> = (0..1000)
+            .map(|_| TimeEntry::new("70:00".parse().unwrap(), None, None))
+            .collect();
+
+        let total = TimeEntry::total(&entries);
+
+        assert_eq!(total.total_minutes(), 70_000 * 60);
+        assert_eq!(total.to_string(), "70000:00");
+        assert_eq!(toml::Value::try_from(total).unwrap().as_str(), Some("70000:00"));
+    }
+
+    #[test]
+    fn durations_carry_minutes() {
+        let total = "1:45".parse::<Duration>().unwrap() + "2:30".parse::<Duration>().unwrap();
+
+        assert_eq!(total.to_string(), "4:15");
+    }
+
+    #[test]
+    fn durations_saturate_rather_than_overflow() {
+        let max = "4294967295:30".parse::<Duration>().unwrap();
+
+        assert_eq!((max + "1:45".parse::<Duration>().unwrap()).to_string(), "4294967295:15");
+    }
+
+    #[test]
+    fn durations_divide_large_values() {
+        let total = Duration::from_minutes(100_000 * 60);
+
+        assert_eq!((total / 4).to_string(), "25000:00");
+        assert_eq!((Duration::from_minutes(10) / 3).total_minutes(), 3);
+    }
+
+    #[test]
+    fn modified_defaults_to_created() {
+        let data = sample();
+
+        assert_eq!(data.modified(), data.created);
+    }
+
+    #[test]
+    fn saving_updates_modified() {
+        let vault = testing::TempVault::new();
+        let mut state = vault.state();
+        let id = vault.new_task("task", &mut state);
+
+        let mut task = Task::load(id, &vault.path, false).unwrap();
+        let modified = task.data.modified.unwrap();
+        assert!(modified >= task.data.created);
+
+        // Saving a task which was last modified long ago moves it forward to now.
+        let old = chrono::NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        task.data.modified = Some(old);
+        task.save().unwrap();
+
+        let task = Task::load(id, &vault.path, false).unwrap();
+        assert!(task.data.modified() >= modified);
+        assert!(task.data.modified() >= task.data.created);
+    }
+
+    #[test]
+    fn rewriting_keeps_modified() {
+        let vault = testing::TempVault::new();
+        let mut state = vault.state();
+        let id = vault.new_task("task", &mut state);
+
+        let old = chrono::NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let mut task = Task::load(id, &vault.path, false).unwrap();
+        task.data.modified = Some(old);
+        task.write().unwrap();
+
+        assert_eq!(Task::load(id, &vault.path, true).unwrap().data.modified, Some(old));
+    }
+
+    #[test]
+    fn atomic_write_replaces_contents() {
+        let vault = testing::TempVault::new();
+        let path = vault.path.join("file.toml");
+        fs::write(&path, "old contents").unwrap();
+
+        write_atomic(&path, "new contents").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new contents");
+        assert!(!vault.path.join("file.toml.tmp").exists());
+    }
+
+    #[test]
+    fn interrupted_save_leaves_task_intact() {
+        let vault = testing::TempVault::new();
+        let mut state = vault.state();
+        let id = vault.new_task("task", &mut state);
+        let path = Task::check_exists(id, &vault.path).unwrap();
+        let original = fs::read_to_string(&path).unwrap();
+
+        // A save interrupted before the rename only leaves a partial temporary file behind.
+        let mut temp_path = path.clone().into_os_string();
+        temp_path.push(".tmp");
+        fs::write(&temp_path, &original[..original.len() / 2]).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert_eq!(Task::load_all(&vault.path, true).unwrap().len(), 1);
+
+        // The next save replaces the leftover temporary file.
+        let mut task = Task::load(id, &vault.path, false).unwrap();
+        task.data.name = String::from("renamed");
+        task.save().unwrap();
+
+        assert_eq!(Task::load(id, &vault.path, true).unwrap().data.name, "renamed");
+        assert!(!path::Path::new(&temp_path).exists());
+    }
+
+    #[test]
+    fn future_completion_is_detected_and_saved() {
+        let vault = testing::TempVault::new();
+        let mut state = vault.state();
+        let id = vault.new_task("task", &mut state);
+
+        let mut task = Task::load(id, &vault.path, false).unwrap();
+        assert!(task.data.future_timestamps().is_empty());
+
+        let completed = chrono::Local::now().naive_local() + chrono::Duration::days(3);
+        task.data.completed = Some(completed);
+        assert_eq!(task.data.future_timestamps(), vec![("completed", completed)]);
+
+        // Only a warning, so the task is still saved.
+        task.save().unwrap();
+        assert_eq!(Task::load(id, &vault.path, true).unwrap().data.completed, Some(completed));
+    }
+
+    #[test]
+    fn cascade_completes_subtasks_but_not_dependencies() {
+        let vault = testing::TempVault::new();
+        let mut state = vault.state();
+        let dependency = vault.new_task("dependency", &mut state);
+        let parent = vault.new_task_with(NewTask { name : String::from("parent"), dependencies : vec![dependency], ..Default::default() }, &mut state);
+        let child = vault.new_task_with(NewTask { name : String::from("child"), parent : Some(parent), ..Default::default() }, &mut state);
+        let grandchild = vault.new_task_with(NewTask { name : String::from("grandchild"), parent : Some(child), ..Default::default() }, &mut state);
+
+        let subtasks = Task::subtasks(parent, &state);
+        assert_eq!(subtasks, vec![child, grandchild]);
+
+        let completed = chrono::Local::now().naive_local();
+        assert_eq!(Task::complete_all(&subtasks, completed, &vault.path, &mut state).unwrap(), 2);
+
+        let is_complete = |id| Task::load(id, &vault.path, true).unwrap().data.completed.is_some();
+        assert!(is_complete(child));
+        assert!(is_complete(grandchild));
+        assert!(!is_complete(dependency));
+        assert!(!is_complete(parent));
+    }
+
+    #[test]
+    fn move_vault_matches_dependencies_by_name() {
+        let source = testing::TempVault::new();
+        let mut source_state = source.state();
+        let dependency = source.new_task("dependency", &mut source_state);
+        let missing = source.new_task("missing", &mut source_state);
+        let id = source.new_task_with(NewTask { name : String::from("moved"), dependencies : vec![dependency, missing], ..Default::default() }, &mut source_state);
+
+        // The destination has an unrelated task with the same ID as the dependency.
+        let destination = testing::TempVault::new();
+        let mut destination_state = destination.state();
+        let unrelated = destination.new_task("unrelated", &mut destination_state);
+        let renumbered = destination.new_task("dependency", &mut destination_state);
+        assert_eq!(unrelated, dependency);
+
+        let new_id = Task::move_vault(id, config::DeleteMode::Remove, &source.path, &mut source_state, &destination.path, destination_state).unwrap();
+
+        let moved = Task::load(new_id, &destination.path, true).unwrap();
+        assert_eq!(moved.data.dependencies, BTreeSet::from([renumbered]));
+        assert!(Task::load(id, &source.path, true).is_err());
+    }
+}
@@ -0,0 +1,51 @@
+use crate::tasks;
+use crate::state;
+use crate::tasks::Id;
+
+use std::fs;
+use std::path;
+use std::process;
+use std::sync::atomic;
+
+/// Used to give each temporary vault within a test run its own folder.
+static NEXT_VAULT : atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+
+/// An empty vault in a temporary folder, which is deleted when dropped.
+pub struct TempVault {
+    pub path : path::PathBuf,
+}
+
+impl TempVault {
+    pub fn new() -> Self {
+        let number = NEXT_VAULT.fetch_add(1, atomic::Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("toru-test-{}-{}", process::id(), number));
+
+        if path.exists() {
+            fs::remove_dir_all(&path).unwrap();
+        }
+        fs::create_dir_all(path.join("tasks")).unwrap();
+
+        Self { path }
+    }
+
+    /// Loads the state of the vault, creating it if this is the first load.
+    pub fn state(&self) -> state::State {
+        state::State::load(&self.path).unwrap()
+    }
+
+    /// Creates a task with the given name and no other details, returning its ID.
+    pub fn new_task(&self, name : &str, state : &mut state::State) -> Id {
+        self.new_task_with(tasks::NewTask { name : String::from(name), ..Default::default() }, state)
+    }
+
+    /// Creates a task with the given details, returning its ID.
+    pub fn new_task_with(&self, new_task : tasks::NewTask, state : &mut state::State) -> Id {
+        tasks::Task::new(new_task, &self.path, state).unwrap()
+    }
+}
+
+impl Drop for TempVault {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
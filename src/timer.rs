@@ -0,0 +1,67 @@
+use crate::tasks;
+use crate::error;
+use crate::state;
+use crate::format;
+
+use std::path;
+
+/// Starts a timer for the specified task, erroring if one is already running.
+pub fn start(id_or_name : &String, vault_folder : &path::Path, state : &mut state::State) -> Result<(), error::Error> {
+    if let Some(timer) = &state.data.timer {
+        return Err(error::Error::Generic(format!("A timer is already running for task {}, stop it first with: {}", format::id(timer.id), format::command("toru track stop"))));
+    }
+
+    let id = state.data.index.lookup(id_or_name)?;
+    let task = tasks::Task::load(id, vault_folder, true)?;
+
+    state.data.timer = Some(state::Timer {
+        id,
+        started : chrono::Local::now().naive_local(),
+    });
+
+    println!("Started timer for task {} (ID: {})", format::task(&task.data.name), format::id(id));
+
+    Ok(())
+}
+
+/// Stops the running timer, adding the elapsed time (to the nearest minute) as a time entry unless
+/// it rounds to zero. Returns whether an entry was added.
+pub fn stop(message : Option<String>, vault_folder : &path::Path, state : &mut state::State) -> Result<bool, error::Error> {
+    let timer = state.data.timer.take().ok_or_else(|| error::Error::Generic(String::from("No timer is running")))?;
+
+    let duration = elapsed(&timer);
+
+    if duration == tasks::Duration::zero() {
+        println!("Stopped timer for task {} without tracking any time, as less than a minute had passed", format::id(timer.id));
+        return Ok(false);
+    }
+
+    let entry = tasks::TimeEntry::new(duration, Some(timer.started.date()), message);
+
+    // The time was already spent, so it is tracked even if the task was completed in the meantime.
+    tasks::Task::track(timer.id, entry, true, vault_folder, state)?;
+
+    println!("Tracked {} against task {}", duration, format::id(timer.id));
+
+    Ok(true)
+}
+
+/// Displays the task the running timer is for and the time elapsed so far.
+pub fn status(vault_folder : &path::Path, state : &state::State) -> Result<(), error::Error> {
+    match &state.data.timer {
+        Some(timer) => {
+            let task = tasks::Task::load(timer.id, vault_folder, true)?;
+            println!("Tracking task {} (ID: {}) for {}", format::task(&task.data.name), format::id(timer.id), elapsed(timer));
+        },
+        None => {
+            println!("No timer is running");
+        }
+    }
+
+    Ok(())
+}
+
+fn elapsed(timer : &state::Timer) -> tasks::Duration {
+    let seconds = (chrono::Local::now().naive_local() - timer.started).num_seconds().max(0);
+    tasks::Duration::from_minutes(u64::try_from((seconds + 30) / 60).unwrap_or(0))
+}
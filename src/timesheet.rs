@@ -0,0 +1,77 @@
+use crate::tasks;
+use crate::error;
+use crate::state;
+use crate::format;
+use crate::journal;
+
+use std::fs;
+use std::path;
+
+/// Imports time entries from a CSV file with rows of the form `id_or_name,date,duration,message`,
+/// where the date and message may be left empty. Each entry is saved as it is read, so rows which
+/// fail do not affect others.
+pub fn import(path : &path::Path, force : bool, vault_folder : &path::Path, state : &mut state::State, journal : &mut journal::Journal) -> Result<(), error::Error> {
+    let contents = fs::read_to_string(path)?;
+
+    let mut imported = 0;
+    let mut failed = 0;
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+
+        // Skip blank lines and comments.
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        match import_row(line, force, vault_folder, state, journal) {
+            Ok(true) => {
+                imported += 1;
+            },
+            Ok(false) => {
+                println!("Skipped line {}", line_number);
+            },
+            Err(err) => {
+                println!("Line {}: {}", line_number, err);
+                failed += 1;
+            },
+        }
+    }
+
+    println!("Imported {} time entr{}", imported, if imported == 1 {"y"} else {"ies"});
+
+    if failed != 0 {
+        Err(error::Error::Generic(format!("{} line{} of {} could not be imported", failed, if failed == 1 {""} else {"s"}, format::file(&path.display().to_string()))))
+    }
+    else {
+        Ok(())
+    }
+}
+
+/// Imports a single row, returning whether the entry was added.
+fn import_row(line : &str, force : bool, vault_folder : &path::Path, state : &mut state::State, journal : &mut journal::Journal) -> Result<bool, error::Error> {
+    // The message is the last field so that it may contain commas.
+    let fields : Vec<&str> = line.splitn(4, ',').map(|f| f.trim()).collect();
+
+    let (id_or_name, date, duration, message) = match fields[..] {
+        [id_or_name, date, duration] => (id_or_name, date, duration, ""),
+        [id_or_name, date, duration, message] => (id_or_name, date, duration, message),
+        _ => return Err(error::Error::Generic(String::from("Expected a row of the form: id_or_name,date,duration,message"))),
+    };
+
+    let id = state.data.index.lookup_exact(&String::from(id_or_name))?;
+
+    let date = if date.is_empty() {
+        None
+    }
+    else {
+        Some(date.parse::<chrono::NaiveDate>().map_err(|err| error::Error::Generic(format!("Invalid date {} - {}", date, err)))?)
+    };
+
+    let duration = duration.parse::<tasks::Duration>().map_err(|err| error::Error::Generic(format!("Invalid duration {} - {}", duration, err)))?;
+
+    let message = if message.is_empty() { None } else { Some(String::from(message)) };
+
+    journal.record(id)?;
+    tasks::Task::track(id, tasks::TimeEntry::new(duration, date, message), force, vault_folder, state)
+}
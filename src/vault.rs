@@ -1,10 +1,13 @@
+use crate::tasks;
 use crate::error;
 use crate::state;
 use crate::format;
 use crate::config;
+use crate::graph;
 
 use std::fs;
 use std::path;
+use std::collections::{HashSet, HashMap, BTreeMap};
 
 pub fn new(name : String, path : path::PathBuf, config : &mut config::Config) -> Result<(), error::Error> {
 
@@ -66,6 +69,8 @@ pub fn connect(name : String, path : path::PathBuf, config : &mut config::Config
     else {
         // Folder exists and contains data.
         if path.exists() && path.is_dir()  {
+            migrate_legacy_layout(&path)?;
+
             // Vault is missing required metadata files.
             if !path.join("tasks").exists() {
                 Err(error::Error::Generic(format!("Cannot connect the vault as it is missing the {} folder", format::file("tasks"))))
@@ -91,14 +96,190 @@ pub fn connect(name : String, path : path::PathBuf, config : &mut config::Config
     }
 }
 
-pub fn disconnect(name : &String, config : &mut config::Config) -> Result<(), error::Error> {
-    config.remove(name)?;
-    Ok(())
+/// Renames the `notes` folder of vaults created with the legacy layout to `tasks`, returning
+/// whether the vault was migrated.
+pub fn migrate_legacy_layout(vault_folder : &path::Path) -> Result<bool, error::Error> {
+    let legacy = vault_folder.join("notes");
+
+    if legacy.is_dir() && !vault_folder.join("tasks").exists() {
+        fs::rename(&legacy, vault_folder.join("tasks"))?;
+        println!("Migrated the {} folder of the vault to {}", format::file("notes"), format::file("tasks"));
+        Ok(true)
+    }
+    else {
+        Ok(false)
+    }
+}
+
+/// Disconnects a vault, returning its resolved name.
+pub fn disconnect(name : &str, config : &mut config::Config) -> Result<String, error::Error> {
+    let (name, _) = config.remove(name)?;
+    Ok(name)
 }
 
-pub fn delete(name : &String, config : &mut config::Config) -> Result<(), error::Error> {
-    let path = config.remove(name)?;
+/// Deletes a vault, returning its resolved name.
+pub fn delete(name : &str, config : &mut config::Config) -> Result<String, error::Error> {
+    let (name, path) = config.remove(name)?;
     trash::delete(path)?;
+    Ok(name)
+}
+
+/// Prints a summary of the vault's tasks, tracked time and tags.
+pub fn info(name : &str, vault_folder : &path::Path) -> Result<(), error::Error> {
+    let state = state::State::load(vault_folder)?;
+    let tasks = tasks::Task::load_all(vault_folder, true)?;
+
+    let completed = tasks.iter().filter(|t| t.data.completed.is_some()).count();
+    let tracked = tasks.iter().fold(tasks::Duration::zero(), |a, t| a + tasks::TimeEntry::total(&t.data.time_entries));
+    let tags : HashSet<_> = tasks.iter().flat_map(|t| t.data.tags.iter()).collect();
+
+    println!("Vault:        {}", format::vault(name));
+    println!("Path:         {}", format::file(&vault_folder.display().to_string()));
+    println!("Tasks:        {} ({} complete, {} incomplete)", tasks.len(), completed, tasks.len() - completed);
+    println!("Tracked:      {}", tracked);
+    println!("Tags:         {}", tags.len());
+    println!("Next ID:      {}", format::id(state.data.next_id));
+
     Ok(())
 }
 
+/// Cross-references the task files against the index and dependency graph, reporting each problem
+/// found. With `fix`, dependencies on missing tasks are removed from the task files and the index,
+/// dependency graph and metadata cache are rebuilt from them.
+pub fn check(fix : bool, vault_folder : &path::Path, state : &mut state::State) -> Result<(), error::Error> {
+    let mut issues = Vec::new();
+    let mut unreadable = 0;
+
+    let mut tasks = HashMap::new();
+    for id in tasks::Task::id_iter(vault_folder) {
+        match tasks::Task::load(id, vault_folder, false) {
+            Ok(task) => {
+                tasks.insert(id, task);
+            },
+            Err(err) => {
+                issues.push(format!("Task file for {} could not be loaded: {}", format::id(id), err));
+                unreadable += 1;
+            }
+        }
+    }
+
+    // Comparing the index with the task files.
+    let indexed : HashSet<_> = state.data.index.entries().map(|(name, id)| (name.clone(), id)).collect();
+    for task in tasks.values() {
+        if !indexed.contains(&(task.data.name.clone(), task.data.id)) {
+            issues.push(format!("Task {} ({}) is missing from the index", format::id(task.data.id), format::task(&task.data.name)));
+        }
+    }
+    for (name, id) in state.data.index.entries() {
+        match tasks.get(&id) {
+            None => issues.push(format!("Index entry {} points at task {}, which does not exist", format::task(name), format::id(id))),
+            Some(task) if task.data.name != *name => issues.push(format!("Index entry {} points at task {}, which is named {}", format::task(name), format::id(id), format::task(&task.data.name))),
+            Some(_) => (),
+        }
+    }
+
+    // Comparing the dependency graph with the task files.
+    for id in tasks.keys() {
+        if !state.data.deps.contains_node(*id) {
+            issues.push(format!("Task {} is missing from the dependency graph", format::id(*id)));
+        }
+    }
+    for (id, dependencies) in &state.data.deps.edges {
+        if !tasks.contains_key(id) {
+            issues.push(format!("Dependency graph contains task {}, which does not exist", format::id(*id)));
+        }
+        for dependency in dependencies.iter().filter(|d| !tasks.contains_key(d)) {
+            issues.push(format!("Dependency graph has task {} depending on {}, which does not exist", format::id(*id), format::id(*dependency)));
+        }
+    }
+    for task in tasks.values() {
+        for dependency in task.data.dependencies.iter().filter(|d| !tasks.contains_key(d)) {
+            issues.push(format!("Task {} depends on {}, which does not exist", format::id(task.data.id), format::id(*dependency)));
+        }
+    }
+
+    // Checking for a cycle in the dependencies listed by the task files, ignoring missing tasks.
+    let graph = graph::Graph {
+        edges : tasks
+            .values()
+            .map(|t| (t.data.id, t.data.dependencies.iter().filter(|d| tasks.contains_key(d)).copied().collect()))
+            .collect::<BTreeMap<_, _>>(),
+    };
+    let cycle = graph.find_cycle();
+    if let Some(cycle) = &cycle {
+        issues.push(format!("Dependencies contain a cycle, which must be removed by editing the tasks: {}", graph::format_cycle(cycle)));
+    }
+
+    if issues.is_empty() {
+        println!("No issues found");
+        return Ok(());
+    }
+
+    for issue in &issues {
+        println!("{} {}", format::warning("Issue:"), issue);
+    }
+
+    if !fix {
+        return Err(error::Error::Generic(format!("{} issue{} found, run {} to repair", issues.len(), if issues.len() == 1 {""} else {"s"}, format::command("toru vault check --fix"))));
+    }
+
+    if unreadable != 0 {
+        return Err(error::Error::Generic(String::from("Task files which could not be loaded must be fixed by hand before repairing")));
+    }
+
+    let ids : HashSet<_> = tasks.keys().copied().collect();
+    for mut task in tasks.into_values() {
+        let count = task.data.dependencies.len();
+        task.data.dependencies.retain(|d| ids.contains(d));
+        if task.data.dependencies.len() != count {
+            task.save()?;
+        }
+    }
+
+    let count = state.reindex()?;
+    println!("Rebuilt the index and dependency graph from {} task{}", count, if count == 1 {""} else {"s"});
+
+    if cycle.is_some() {
+        Err(error::Error::Generic(String::from("The dependency cycle could not be repaired automatically")))
+    }
+    else {
+        Ok(())
+    }
+}
+
+/// Rewrites every task file in the vault in the canonical format, reporting any which fail to
+/// load.
+pub fn tidy(vault_folder : &path::Path) -> Result<(), error::Error> {
+    let mut total = 0;
+    let mut changed = 0;
+    let mut failed = 0;
+
+    for id in tasks::Task::id_iter(vault_folder) {
+        total += 1;
+
+        match tasks::Task::load(id, vault_folder, false) {
+            Ok(task) => {
+                let path = task.path.clone();
+                let before = fs::read_to_string(&path)?;
+                task.write()?;
+
+                if fs::read_to_string(&path)? != before {
+                    changed += 1;
+                }
+            },
+            Err(err) => {
+                println!("Failed to load task {}: {}", format::id(id), err);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("Reformatted {} of {} task files", changed, total);
+
+    if failed != 0 {
+        Err(error::Error::Generic(format!("{} task file{} could not be loaded", failed, if failed == 1 {""} else {"s"})))
+    }
+    else {
+        Ok(())
+    }
+}
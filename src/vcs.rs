@@ -27,14 +27,14 @@ pub fn command(args : Vec<String>, vcs : Vcs, vault_folder : &path::Path) -> Res
 }
 
 pub fn create_gitignore(vault_folder : &path::Path) -> Result<(), error::Error> {
-    Ok(fs::write(vault_folder.join(".gitignore"), "temp.toml\ntemp.md")?)
+    Ok(fs::write(vault_folder.join(".gitignore"), "temp.toml\ntemp.md\n*.tmp")?)
 }
 
 pub fn set_svn_ignore(vault_folder : &path::Path) -> Result<(), error::Error> {
 
     let mut child = process::Command::new("svn")
         .current_dir(vault_folder)
-        .args(&["propset", "svn:ignore", "temp.toml\ntemp.md", "."])
+        .args(&["propset", "svn:ignore", "temp.toml\ntemp.md\n*.tmp", "."])
         .spawn()?;
 
     let _ = child.wait()?;